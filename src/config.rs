@@ -0,0 +1,198 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use twilight_model::{
+    channel::{
+        message::{AllowedMentions, MentionType},
+        Channel,
+    },
+    id::{
+        marker::{ChannelMarker, GuildMarker, RoleMarker},
+        Id,
+    },
+};
+
+use crate::file;
+
+/// Which mentions in moved messages ping again
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionPolicy {
+    #[default]
+    None,
+    Users,
+}
+
+impl MentionPolicy {
+    pub fn allowed_mentions(self) -> AllowedMentions {
+        AllowedMentions {
+            parse: match self {
+                Self::None => vec![],
+                Self::Users => vec![MentionType::Users],
+            },
+            ..AllowedMentions::default()
+        }
+    }
+}
+
+/// What to do with messages that are too long for the bot to send, because the
+/// author has Nitro
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LongMessageStrategy {
+    #[default]
+    Split,
+    Embed,
+    File,
+}
+
+/// Whether to leave a notice in the source channel after moving messages
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceNotice {
+    #[default]
+    Disabled,
+    Permanent,
+    AutoDelete,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GuildConfig {
+    pub mention_policy: MentionPolicy,
+    pub long_message_strategy: LongMessageStrategy,
+    pub max_messages: usize,
+    /// How long moves can be undone for, `0` disables undoing
+    pub undo_window_minutes: u64,
+    /// How long prompts wait for the user to respond
+    pub prompt_timeout_minutes: u64,
+    pub source_notice: SourceNotice,
+    /// How long auto-deleting source notices stay
+    pub source_notice_delete_after_minutes: u64,
+    /// Whether to list the reactions on moved messages under the copies
+    pub reaction_summary: bool,
+    /// Whether to show when moved messages were originally sent under the
+    /// copies
+    pub original_timestamps: bool,
+    /// The channels messages can be moved to, empty to allow all channels
+    pub allowed_channel_ids: Vec<Id<ChannelMarker>>,
+    /// The roles that can move messages, empty to allow everyone
+    pub allowed_role_ids: Vec<Id<RoleMarker>>,
+    /// The roles that can move other people's messages without **Manage
+    /// Messages** permissions
+    pub mover_role_ids: Vec<Id<RoleMarker>>,
+    /// The roles that can move other people's messages in the channel, used
+    /// instead of `mover_role_ids` for the channels in it
+    pub channel_mover_role_ids: HashMap<Id<ChannelMarker>, Vec<Id<RoleMarker>>>,
+    /// The role that can't move messages at all
+    pub denied_role_id: Option<Id<RoleMarker>>,
+}
+
+impl GuildConfig {
+    /// Whether messages can be moved to the channel, threads are allowed if
+    /// their parent is
+    pub fn is_destination_allowed(&self, channel: &Channel) -> bool {
+        self.allowed_channel_ids.is_empty()
+            || self.allowed_channel_ids.contains(&channel.id)
+            || channel
+                .parent_id
+                .is_some_and(|parent_id| self.allowed_channel_ids.contains(&parent_id))
+    }
+
+    /// Whether the member has the role that can't move messages
+    pub fn is_denied(&self, role_ids: &[Id<RoleMarker>]) -> bool {
+        self.denied_role_id
+            .is_some_and(|denied_role_id| role_ids.contains(&denied_role_id))
+    }
+
+    /// Whether the member has one of the roles that can move messages
+    pub fn is_role_allowed(&self, role_ids: &[Id<RoleMarker>]) -> bool {
+        self.allowed_role_ids.is_empty()
+            || role_ids
+                .iter()
+                .any(|role_id| self.allowed_role_ids.contains(role_id))
+    }
+
+    /// Whether the member has one of the roles that can move other people's
+    /// messages in the channel, threads use the roles of their parent if they
+    /// don't have their own
+    pub fn is_mover(&self, role_ids: &[Id<RoleMarker>], channel: &Channel) -> bool {
+        let mover_role_ids = self
+            .channel_mover_role_ids
+            .get(&channel.id)
+            .or_else(|| {
+                channel
+                    .parent_id
+                    .and_then(|parent_id| self.channel_mover_role_ids.get(&parent_id))
+            })
+            .unwrap_or(&self.mover_role_ids);
+
+        role_ids
+            .iter()
+            .any(|role_id| mover_role_ids.contains(role_id))
+    }
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        Self {
+            mention_policy: MentionPolicy::default(),
+            long_message_strategy: LongMessageStrategy::default(),
+            max_messages: 100,
+            undo_window_minutes: 10,
+            prompt_timeout_minutes: 5,
+            source_notice: SourceNotice::default(),
+            source_notice_delete_after_minutes: 5,
+            reaction_summary: false,
+            original_timestamps: false,
+            allowed_channel_ids: vec![],
+            allowed_role_ids: vec![],
+            mover_role_ids: vec![],
+            channel_mover_role_ids: HashMap::new(),
+            denied_role_id: None,
+        }
+    }
+}
+
+/// The configs of guilds, saved to a JSON file whenever they're updated
+pub struct Config {
+    path: String,
+    guilds: Mutex<HashMap<Id<GuildMarker>, GuildConfig>>,
+}
+
+impl Config {
+    pub fn load(path: String) -> Result<Self> {
+        Ok(Self {
+            guilds: Mutex::new(file::load(&path)?),
+            path,
+        })
+    }
+
+    pub fn guild(&self, guild_id: Id<GuildMarker>) -> Result<GuildConfig> {
+        Ok(self
+            .guilds
+            .lock()
+            .map_err(|_| anyhow!("config mutex is poisoned"))?
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    pub fn update(
+        &self,
+        guild_id: Id<GuildMarker>,
+        update: impl FnOnce(&mut GuildConfig),
+    ) -> Result<()> {
+        let mut guilds = self
+            .guilds
+            .lock()
+            .map_err(|_| anyhow!("config mutex is poisoned"))?;
+
+        update(guilds.entry(guild_id).or_default());
+        file::save(&self.path, &*guilds)?;
+        drop(guilds);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use std::{fs, io::ErrorKind};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Read the JSON file, returning the default value if it doesn't exist
+///
+/// If the file can't be parsed, it's renamed to `<path>.corrupt` so that it's
+/// not overwritten and the default value is returned
+pub fn load<T: DeserializeOwned + Default>(path: &str) -> Result<T> {
+    let json = match fs::read(path) {
+        Ok(json) => json,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => return Err(err.into()),
+    };
+
+    match serde_json::from_slice(&json) {
+        Ok(value) => Ok(value),
+        Err(err) => {
+            let corrupt_path = format!("{path}.corrupt");
+            eprintln!("{path} is corrupt, moving it to {corrupt_path} and starting fresh: {err}");
+            fs::rename(path, corrupt_path)?;
+            Ok(T::default())
+        }
+    }
+}
+
+/// Write the value to the JSON file, writing to a temporary file first and
+/// renaming it so that the file isn't left half-written if the bot stops
+pub fn save(path: &str, value: &impl Serialize) -> Result<()> {
+    let tmp_path = format!("{path}.tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(tmp_path, path)?;
+
+    Ok(())
+}
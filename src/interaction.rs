@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use anyhow::Result;
 use sparkle_convenience::{
     error::IntoError,
@@ -6,33 +8,83 @@ use sparkle_convenience::{
 };
 use twilight_model::application::interaction::Interaction;
 
-use crate::{err_reply, Context, CustomError, Error, TEST_GUILD_ID};
+use crate::{err_reply, job::Mode, Context, CustomError, Error, TEST_GUILD_ID};
 
 mod channel_select_menu;
+mod command_options;
+mod config;
+mod confirm;
+mod destination;
+mod forum;
 mod message_command;
 mod move_channel_select;
+mod move_last_messages;
 mod move_message;
 mod move_message_and_below;
+mod move_messages_between;
+mod new_thread;
+mod preview;
+mod source_notice;
+mod undo;
+
+pub use undo::LastMove;
 
 struct InteractionContext<'ctx> {
     ctx: &'ctx Context,
     handle: InteractionHandle<'ctx>,
     interaction: Interaction,
+    /// When the interaction was received, which its token expires relative to
+    received_at: Instant,
 }
 
-impl<'ctx> InteractionContext<'ctx> {
+impl InteractionContext<'_> {
     async fn handle(self) -> Result<()> {
         match self.interaction.name().ok()? {
-            move_message::NAME => self.handle_move_message_command().await,
-            move_message_and_below::NAME => self.handle_move_message_and_below_command().await,
-            move_channel_select::CUSTOM_ID => Ok(()),
+            move_message::NAME => Box::pin(self.handle_move_message_command(Mode::Move)).await,
+            move_message::COPY_NAME => Box::pin(self.handle_move_message_command(Mode::Copy)).await,
+            move_message_and_below::NAME => {
+                Box::pin(self.handle_move_message_and_below_command(Mode::Move)).await
+            }
+            move_message_and_below::COPY_NAME => {
+                Box::pin(self.handle_move_message_and_below_command(Mode::Copy)).await
+            }
+            move_messages_between::START_NAME => {
+                self.handle_move_messages_from_here_command().await
+            }
+            move_messages_between::END_NAME => {
+                Box::pin(self.handle_move_messages_until_here_command()).await
+            }
+            move_last_messages::NAME => Box::pin(self.handle_move_command()).await,
+            custom_id if custom_id.starts_with(undo::CUSTOM_ID) => {
+                Box::pin(self.handle_undo_button()).await
+            }
+            config::NAME => self.handle_config_command().await,
+            move_channel_select::CUSTOM_ID
+            | move_channel_select::AUTHOR_SELECT_CUSTOM_ID
+            | move_channel_select::CANCEL_CUSTOM_ID
+            | move_channel_select::NEW_THREAD_HERE_CUSTOM_ID
+            | move_channel_select::NEW_THREAD_CHANNEL_CUSTOM_ID
+            | new_thread::MODAL_CUSTOM_ID
+            | forum::TAG_SELECT_CUSTOM_ID
+            | forum::TAGS_DONE_CUSTOM_ID
+            | confirm::CONFIRM_CUSTOM_ID
+            | confirm::CANCEL_CUSTOM_ID => Ok(()),
             name => Err(Error::UnknownCommand(name.to_owned()).into()),
         }
     }
 }
 
 pub async fn set_commands(bot: &Bot) -> Result<()> {
-    let commands = &[move_message::command(), move_message_and_below::command()];
+    let commands = &[
+        move_message::command(),
+        move_message_and_below::command(),
+        move_message::copy_command(),
+        move_message_and_below::copy_command(),
+        move_messages_between::start_command(),
+        move_messages_between::end_command(),
+        move_last_messages::command(),
+        config::command(),
+    ];
 
     bot.interaction_client()
         .set_global_commands(commands)
@@ -51,9 +103,10 @@ impl Context {
             ctx: self,
             handle: handle.clone(),
             interaction,
+            received_at: Instant::now(),
         };
 
-        if let Err(err) = ctx.handle().await {
+        if let Err(err) = Box::pin(ctx.handle()).await {
             handle
                 .handle_error::<CustomError>(err_reply(&err), err)
                 .await;
@@ -3,12 +3,16 @@ use serde::Serialize;
 use sparkle_convenience::interaction::DeferVisibility;
 use twilight_http::Response;
 use twilight_model::channel::{
-    message::{component::ComponentType, MessageFlags},
+    message::{
+        component::{self, Button, ComponentType},
+        Component, MessageFlags,
+    },
     ChannelType, Message,
 };
 
 use crate::interaction::InteractionContext;
 
+const USER_SELECT_MENU_TYPE: u8 = 5;
 const CHANNEL_SELECT_MENU_TYPE: u8 = 8;
 
 #[derive(Serialize)]
@@ -16,47 +20,105 @@ pub struct ChannelSelectMenu {
     #[serde(rename = "type")]
     kind: u8,
     custom_id: String,
+    placeholder: String,
     channel_types: Vec<ChannelType>,
 }
 
 impl ChannelSelectMenu {
-    pub fn new(custom_id: String, channel_types: Vec<ChannelType>) -> Self {
+    pub const fn new(
+        custom_id: String,
+        placeholder: String,
+        channel_types: Vec<ChannelType>,
+    ) -> Self {
         Self {
             kind: CHANNEL_SELECT_MENU_TYPE,
             custom_id,
+            placeholder,
             channel_types,
         }
     }
 }
 
+#[derive(Serialize)]
+pub struct UserSelectMenu {
+    #[serde(rename = "type")]
+    kind: u8,
+    custom_id: String,
+    placeholder: String,
+    min_values: u8,
+    max_values: u8,
+}
+
+impl UserSelectMenu {
+    /// Create a user select menu that allows selecting no users or up to 25
+    pub const fn new(custom_id: String, placeholder: String) -> Self {
+        Self {
+            kind: USER_SELECT_MENU_TYPE,
+            custom_id,
+            placeholder,
+            min_values: 0,
+            max_values: 25,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum SelectMenu {
+    Channel(ChannelSelectMenu),
+    User(UserSelectMenu),
+}
+
 #[derive(Serialize)]
 struct ActionRow {
     #[serde(rename = "type")]
     kind: u8,
-    components: Vec<ChannelSelectMenu>,
+    components: Vec<SelectMenu>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Row {
+    SelectMenu(ActionRow),
+    Buttons(Component),
 }
 
 #[derive(Serialize)]
 struct InteractionResponse {
     content: String,
     flags: Option<MessageFlags>,
-    components: Vec<ActionRow>,
+    components: Vec<Row>,
 }
 
 impl InteractionContext<'_> {
-    pub async fn followup_with_channel_select_menu(
+    /// Send a followup with each select menu in its own row and the buttons
+    /// in the last row
+    pub async fn followup_with_select_menus(
         &self,
         content: String,
         visibility: DeferVisibility,
-        menu: ChannelSelectMenu,
+        menus: Vec<SelectMenu>,
+        buttons: Vec<Button>,
     ) -> Result<Response<Message>> {
+        let mut components = menus
+            .into_iter()
+            .map(|menu| {
+                Row::SelectMenu(ActionRow {
+                    kind: ComponentType::ActionRow.into(),
+                    components: vec![menu],
+                })
+            })
+            .collect::<Vec<_>>();
+        if !buttons.is_empty() {
+            components.push(Row::Buttons(Component::ActionRow(component::ActionRow {
+                components: buttons.into_iter().map(Component::Button).collect(),
+            })));
+        }
+
         let response = InteractionResponse {
             content,
             flags: (visibility == DeferVisibility::Ephemeral).then_some(MessageFlags::EPHEMERAL),
-            components: vec![ActionRow {
-                kind: ComponentType::ActionRow.into(),
-                components: vec![menu],
-            }],
+            components,
         };
 
         Ok(self
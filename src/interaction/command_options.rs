@@ -0,0 +1,49 @@
+use twilight_model::{
+    application::interaction::application_command::{CommandDataOption, CommandOptionValue},
+    id::{
+        marker::{ChannelMarker, RoleMarker, UserMarker},
+        Id,
+    },
+};
+
+pub fn string_option(options: &[CommandDataOption], name: &str) -> Option<String> {
+    options.iter().find_map(|option| match &option.value {
+        CommandOptionValue::String(value) if option.name == name => Some(value.clone()),
+        _ => None,
+    })
+}
+
+pub fn integer_option(options: &[CommandDataOption], name: &str) -> Option<i64> {
+    options.iter().find_map(|option| match option.value {
+        CommandOptionValue::Integer(value) if option.name == name => Some(value),
+        _ => None,
+    })
+}
+
+pub fn boolean_option(options: &[CommandDataOption], name: &str) -> Option<bool> {
+    options.iter().find_map(|option| match option.value {
+        CommandOptionValue::Boolean(value) if option.name == name => Some(value),
+        _ => None,
+    })
+}
+
+pub fn channel_option(options: &[CommandDataOption], name: &str) -> Option<Id<ChannelMarker>> {
+    options.iter().find_map(|option| match option.value {
+        CommandOptionValue::Channel(value) if option.name == name => Some(value),
+        _ => None,
+    })
+}
+
+pub fn role_option(options: &[CommandDataOption], name: &str) -> Option<Id<RoleMarker>> {
+    options.iter().find_map(|option| match option.value {
+        CommandOptionValue::Role(value) if option.name == name => Some(value),
+        _ => None,
+    })
+}
+
+pub fn user_option(options: &[CommandDataOption], name: &str) -> Option<Id<UserMarker>> {
+    options.iter().find_map(|option| match option.value {
+        CommandOptionValue::User(value) if option.name == name => Some(value),
+        _ => None,
+    })
+}
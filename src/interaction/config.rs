@@ -0,0 +1,680 @@
+use anyhow::Result;
+use sparkle_convenience::{
+    error::IntoError, interaction::extract::InteractionDataExt, reply::Reply,
+};
+use twilight_model::{
+    application::{
+        command::{Command, CommandType},
+        interaction::application_command::{CommandDataOption, CommandOptionValue},
+    },
+    channel::message::AllowedMentions,
+    guild::Permissions,
+    id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::command::{
+    BooleanBuilder, ChannelBuilder, CommandBuilder, IntegerBuilder, RoleBuilder, StringBuilder,
+    SubCommandBuilder,
+};
+
+use crate::{
+    config::{GuildConfig, LongMessageStrategy, MentionPolicy, SourceNotice},
+    interaction::{
+        command_options::{
+            boolean_option, channel_option, integer_option, role_option, string_option,
+        },
+        move_channel_select::MOVE_CHANNEL_TYPES,
+        InteractionContext,
+    },
+    Error,
+};
+
+pub const NAME: &str = "config";
+
+const MENTIONS: &str = "mentions";
+const LONG_MESSAGES: &str = "long-messages";
+const MAX_MESSAGES: &str = "max-messages";
+const UNDO_WINDOW: &str = "undo-window";
+const PROMPT_TIMEOUT: &str = "prompt-timeout";
+const SOURCE_NOTICE: &str = "source-notice";
+const REACTION_SUMMARY: &str = "reaction-summary";
+const TIMESTAMPS: &str = "timestamps";
+const ALLOWED_CHANNELS: &str = "allowed-channels";
+const ALLOWED_ROLES: &str = "allowed-roles";
+const MOVER_ROLES: &str = "mover-roles";
+const DENIED_ROLE: &str = "denied-role";
+const SHOW: &str = "show";
+
+/// The choices of the options to change the allowed channels and roles
+const LIST_ACTION_CHOICES: [(&str, &str); 3] = [
+    ("add", "add"),
+    ("remove", "remove"),
+    ("clear the list to allow all", "clear"),
+];
+
+/// The highest limit of messages to move at once admins can set, the limit is
+/// lowered further if the prompt timeout leaves less time to move them
+pub const MAX_MESSAGES_LIMIT: i64 = 300;
+
+/// The most minutes admins can set source notices to be deleted after, since
+/// the deletion is lost if the bot restarts before it
+pub const SOURCE_NOTICE_DELETE_AFTER_LIMIT: i64 = 60;
+
+pub fn command() -> Command {
+    CommandBuilder::new(
+        NAME,
+        "change how i work in this server",
+        CommandType::ChatInput,
+    )
+    .dm_permission(false)
+    .default_member_permissions(Permissions::MANAGE_GUILD)
+    .option(
+        SubCommandBuilder::new(MENTIONS, "which mentions in moved messages ping again").option(
+            StringBuilder::new("policy", "the mentions that ping again")
+                .required(true)
+                .choices([("none", "none"), ("only users", "users")]),
+        ),
+    )
+    .option(
+        SubCommandBuilder::new(
+            LONG_MESSAGES,
+            "what to do with messages that are too long for me to send",
+        )
+        .option(
+            StringBuilder::new("strategy", "how to send the long messages")
+                .required(true)
+                .choices([
+                    ("split into multiple messages", "split"),
+                    ("put in an embed", "embed"),
+                    ("attach as a text file", "file"),
+                ]),
+        ),
+    )
+    .option(
+        SubCommandBuilder::new(MAX_MESSAGES, "the most messages i can move at once").option(
+            IntegerBuilder::new("limit", "the most messages i can move at once")
+                .required(true)
+                .min_value(1)
+                .max_value(MAX_MESSAGES_LIMIT),
+        ),
+    )
+    .option(
+        SubCommandBuilder::new(UNDO_WINDOW, "how long moves can be undone for").option(
+            IntegerBuilder::new(
+                "minutes",
+                "how many minutes moves can be undone for, 0 to disable",
+            )
+            .required(true)
+            .min_value(0)
+            .max_value(24 * 60),
+        ),
+    )
+    .option(
+        SubCommandBuilder::new(
+            PROMPT_TIMEOUT,
+            "how long i wait for you to select the channel or confirm",
+        )
+        .option(
+            IntegerBuilder::new("minutes", "how many minutes i wait for you")
+                .required(true)
+                .min_value(1)
+                // interaction tokens expire after 15 minutes, the time left after the
+                // prompts limits how many messages can be moved
+                .max_value(14),
+        ),
+    )
+    .option(source_notice_subcommand())
+    .option(
+        SubCommandBuilder::new(
+            REACTION_SUMMARY,
+            "whether i list the reactions on moved messages under them",
+        )
+        .option(BooleanBuilder::new("enabled", "whether to list the reactions").required(true)),
+    )
+    .option(
+        SubCommandBuilder::new(
+            TIMESTAMPS,
+            "whether i show when moved messages were originally sent",
+        )
+        .option(
+            BooleanBuilder::new("enabled", "whether to show the original times").required(true),
+        ),
+    )
+    .option(allowed_channels_subcommand())
+    .option(allowed_roles_subcommand())
+    .option(mover_roles_subcommand())
+    .option(
+        SubCommandBuilder::new(
+            DENIED_ROLE,
+            "the role that can't move messages at all, even if they can manage the server",
+        )
+        .option(RoleBuilder::new(
+            "role",
+            "the role, leave empty to not deny any role",
+        )),
+    )
+    .option(SubCommandBuilder::new(
+        SHOW,
+        "show how i work in this server",
+    ))
+    .build()
+}
+
+fn source_notice_subcommand() -> SubCommandBuilder {
+    SubCommandBuilder::new(
+        SOURCE_NOTICE,
+        "whether i leave a notice where the messages were moved from",
+    )
+    .option(
+        StringBuilder::new("notice", "whether to leave a notice")
+            .required(true)
+            .choices([
+                ("don't leave a notice", "disabled"),
+                ("leave a notice", "permanent"),
+                ("leave a notice and delete it after a while", "auto_delete"),
+            ]),
+    )
+    .option(
+        IntegerBuilder::new(
+            "delete-after",
+            "how many minutes to delete the notice after",
+        )
+        .min_value(1)
+        .max_value(SOURCE_NOTICE_DELETE_AFTER_LIMIT),
+    )
+}
+
+fn allowed_channels_subcommand() -> SubCommandBuilder {
+    SubCommandBuilder::new(ALLOWED_CHANNELS, "the channels messages can be moved to")
+        .option(
+            StringBuilder::new("action", "what to do with the channel")
+                .required(true)
+                .choices(LIST_ACTION_CHOICES),
+        )
+        .option(
+            ChannelBuilder::new("channel", "the channel to add or remove")
+                .channel_types(MOVE_CHANNEL_TYPES),
+        )
+}
+
+fn mover_roles_subcommand() -> SubCommandBuilder {
+    SubCommandBuilder::new(
+        MOVER_ROLES,
+        "the roles that can move other people's messages without manage messages permissions",
+    )
+    .option(
+        StringBuilder::new("action", "what to do with the role")
+            .required(true)
+            .choices(LIST_ACTION_CHOICES),
+    )
+    .option(RoleBuilder::new("role", "the role to add or remove"))
+    .option(
+        ChannelBuilder::new(
+            "channel",
+            "the channel to change the roles in, instead of the whole server",
+        )
+        .channel_types(MOVE_CHANNEL_TYPES),
+    )
+}
+
+fn allowed_roles_subcommand() -> SubCommandBuilder {
+    SubCommandBuilder::new(ALLOWED_ROLES, "the roles that can move messages")
+        .option(
+            StringBuilder::new("action", "what to do with the role")
+                .required(true)
+                .choices(LIST_ACTION_CHOICES),
+        )
+        .option(RoleBuilder::new("role", "the role to add or remove"))
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_config_command(self) -> Result<()> {
+        let guild_id = self.interaction.guild_id.ok()?;
+
+        let subcommand = self
+            .interaction
+            .data
+            .clone()
+            .ok()?
+            .command()
+            .ok()?
+            .options
+            .into_iter()
+            .next()
+            .ok()?;
+        let CommandOptionValue::SubCommand(options) = subcommand.value else {
+            return Err(Error::UnknownCommand(subcommand.name).into());
+        };
+
+        let reply_content = match subcommand.name.as_str() {
+            MENTIONS => self.set_mention_policy(guild_id, &options)?,
+            LONG_MESSAGES => self.set_long_message_strategy(guild_id, &options)?,
+            MAX_MESSAGES => self.set_max_messages(guild_id, &options)?,
+            UNDO_WINDOW => self.set_undo_window(guild_id, &options)?,
+            PROMPT_TIMEOUT => self.set_prompt_timeout(guild_id, &options)?,
+            SOURCE_NOTICE => self.set_source_notice(guild_id, &options)?,
+            REACTION_SUMMARY => self.set_reaction_summary(guild_id, &options)?,
+            TIMESTAMPS => self.set_original_timestamps(guild_id, &options)?,
+            ALLOWED_CHANNELS => self.set_allowed_channels(guild_id, &options)?,
+            ALLOWED_ROLES => self.set_allowed_roles(guild_id, &options)?,
+            MOVER_ROLES => self.set_mover_roles(guild_id, &options)?,
+            DENIED_ROLE => self.set_denied_role(guild_id, &options)?,
+            SHOW => self.show_config(guild_id)?,
+            name => return Err(Error::UnknownCommand(name.to_owned()).into()),
+        };
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .allowed_mentions(Some(AllowedMentions::default()))
+                    .content(reply_content),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    fn set_mention_policy(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let mention_policy = match string_option(options, "policy").ok()?.as_str() {
+            "users" => MentionPolicy::Users,
+            _ => MentionPolicy::None,
+        };
+        self.ctx.config.update(guild_id, |config| {
+            config.mention_policy = mention_policy;
+        })?;
+
+        Ok(match mention_policy {
+            MentionPolicy::None => "got it, mentions in moved messages won't ping anyone",
+            MentionPolicy::Users => {
+                "got it, user mentions in moved messages will ping them again, other mentions \
+                 won't"
+            }
+        }
+        .to_owned())
+    }
+
+    fn set_long_message_strategy(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let long_message_strategy = match string_option(options, "strategy").ok()?.as_str() {
+            "embed" => LongMessageStrategy::Embed,
+            "file" => LongMessageStrategy::File,
+            _ => LongMessageStrategy::Split,
+        };
+        self.ctx.config.update(guild_id, |config| {
+            config.long_message_strategy = long_message_strategy;
+        })?;
+
+        Ok(match long_message_strategy {
+            LongMessageStrategy::Split => {
+                "got it, long messages will be split into multiple messages"
+            }
+            LongMessageStrategy::Embed => {
+                "got it, long messages will be put in an embed, or sent as a file if they're too \
+                 long for one"
+            }
+            LongMessageStrategy::File => "got it, long messages will be attached as a text file",
+        }
+        .to_owned())
+    }
+
+    fn set_max_messages(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let max_messages = integer_option(options, "limit").ok()?.try_into()?;
+        self.ctx.config.update(guild_id, |config| {
+            config.max_messages = max_messages;
+        })?;
+
+        Ok(format!(
+            "got it, i'll move up to {max_messages} messages at once"
+        ))
+    }
+
+    fn set_undo_window(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let undo_window_minutes = integer_option(options, "minutes").ok()?.try_into()?;
+        self.ctx.config.update(guild_id, |config| {
+            config.undo_window_minutes = undo_window_minutes;
+        })?;
+
+        Ok(if undo_window_minutes == 0 {
+            "got it, moves can't be undone".to_owned()
+        } else {
+            format!("got it, moves can be undone for {undo_window_minutes} minutes")
+        })
+    }
+
+    fn set_prompt_timeout(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let prompt_timeout_minutes = integer_option(options, "minutes").ok()?.try_into()?;
+        self.ctx.config.update(guild_id, |config| {
+            config.prompt_timeout_minutes = prompt_timeout_minutes;
+        })?;
+
+        Ok(format!(
+            "got it, i'll wait for {prompt_timeout_minutes} minutes for you to respond"
+        ))
+    }
+
+    fn set_source_notice(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let source_notice = match string_option(options, "notice").ok()?.as_str() {
+            "permanent" => SourceNotice::Permanent,
+            "auto_delete" => SourceNotice::AutoDelete,
+            _ => SourceNotice::Disabled,
+        };
+        let delete_after_minutes = integer_option(options, "delete-after")
+            .map(u64::try_from)
+            .transpose()?;
+
+        let mut config_delete_after_minutes = 0;
+        self.ctx.config.update(guild_id, |config| {
+            config.source_notice = source_notice;
+            if let Some(delete_after_minutes) = delete_after_minutes {
+                config.source_notice_delete_after_minutes = delete_after_minutes;
+            }
+            config_delete_after_minutes = config.source_notice_delete_after_minutes;
+        })?;
+
+        Ok(match source_notice {
+            SourceNotice::Disabled => {
+                "got it, i won't leave a notice where messages are moved from".to_owned()
+            }
+            SourceNotice::Permanent => {
+                "got it, i'll leave a notice where messages are moved from".to_owned()
+            }
+            SourceNotice::AutoDelete => format!(
+                "got it, i'll leave a notice where messages are moved from and delete it after \
+                 {config_delete_after_minutes} minutes"
+            ),
+        })
+    }
+
+    fn set_reaction_summary(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let reaction_summary = boolean_option(options, "enabled").ok()?;
+        self.ctx.config.update(guild_id, |config| {
+            config.reaction_summary = reaction_summary;
+        })?;
+
+        Ok(if reaction_summary {
+            "got it, i'll list the reactions on moved messages under them"
+        } else {
+            "got it, i won't list the reactions on moved messages"
+        }
+        .to_owned())
+    }
+
+    fn set_original_timestamps(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let original_timestamps = boolean_option(options, "enabled").ok()?;
+        self.ctx.config.update(guild_id, |config| {
+            config.original_timestamps = original_timestamps;
+        })?;
+
+        Ok(if original_timestamps {
+            "got it, i'll show when moved messages were originally sent under them"
+        } else {
+            "got it, i won't show when moved messages were originally sent"
+        }
+        .to_owned())
+    }
+
+    fn set_allowed_channels(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let action = string_option(options, "action").ok()?;
+        let channel_id = channel_option(options, "channel");
+        if action != "clear" && channel_id.is_none() {
+            return Ok("please select the channel to add or remove".to_owned());
+        }
+
+        let mut allowed_channel_ids = vec![];
+        self.ctx.config.update(guild_id, |config| {
+            update_list(&mut config.allowed_channel_ids, &action, channel_id);
+            allowed_channel_ids.clone_from(&config.allowed_channel_ids);
+        })?;
+
+        Ok(if allowed_channel_ids.is_empty() {
+            "got it, messages can be moved to any channel".to_owned()
+        } else {
+            format!(
+                "got it, messages can only be moved to {}",
+                mentions(&allowed_channel_ids, "#")
+            )
+        })
+    }
+
+    fn set_allowed_roles(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let action = string_option(options, "action").ok()?;
+        let role_id = role_option(options, "role");
+        if action != "clear" && role_id.is_none() {
+            return Ok("please select the role to add or remove".to_owned());
+        }
+
+        let mut allowed_role_ids = vec![];
+        self.ctx.config.update(guild_id, |config| {
+            update_list(&mut config.allowed_role_ids, &action, role_id);
+            allowed_role_ids.clone_from(&config.allowed_role_ids);
+        })?;
+
+        Ok(if allowed_role_ids.is_empty() {
+            "got it, everyone can move messages".to_owned()
+        } else {
+            format!(
+                "got it, only {} and members who can manage the server can move messages",
+                mentions(&allowed_role_ids, "@&")
+            )
+        })
+    }
+
+    fn set_mover_roles(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let action = string_option(options, "action").ok()?;
+        let role_id = role_option(options, "role");
+        let channel_id = channel_option(options, "channel");
+        if action != "clear" && role_id.is_none() {
+            return Ok("please select the role to add or remove".to_owned());
+        }
+
+        let mut mover_role_ids = vec![];
+        self.ctx.config.update(guild_id, |config| {
+            if let Some(channel_id) = channel_id {
+                let channel_mover_role_ids =
+                    config.channel_mover_role_ids.entry(channel_id).or_default();
+                update_list(channel_mover_role_ids, &action, role_id);
+                mover_role_ids.clone_from(channel_mover_role_ids);
+                if action == "clear" {
+                    config.channel_mover_role_ids.remove(&channel_id);
+                }
+            } else {
+                update_list(&mut config.mover_role_ids, &action, role_id);
+                mover_role_ids.clone_from(&config.mover_role_ids);
+            }
+        })?;
+
+        let place = channel_id.map_or_else(
+            || "this server".to_owned(),
+            |channel_id| format!("<#{channel_id}>"),
+        );
+        Ok(
+            if mover_role_ids.is_empty() && channel_id.is_some() && action == "clear" {
+                format!("got it, {place} uses the roles of the whole server")
+            } else if mover_role_ids.is_empty() {
+                format!(
+                "got it, only members with manage messages permissions can move other people's \
+                 messages in {place}"
+            )
+            } else {
+                format!(
+                    "got it, {} can move other people's messages in {place}",
+                    mentions(&mover_role_ids, "@&")
+                )
+            },
+        )
+    }
+
+    fn set_denied_role(
+        &self,
+        guild_id: Id<GuildMarker>,
+        options: &[CommandDataOption],
+    ) -> Result<String> {
+        let denied_role_id = role_option(options, "role");
+        self.ctx.config.update(guild_id, |config| {
+            config.denied_role_id = denied_role_id;
+        })?;
+
+        Ok(denied_role_id.map_or_else(
+            || "got it, no role is denied from moving messages".to_owned(),
+            |role_id| format!("got it, <@&{role_id}> can't move messages"),
+        ))
+    }
+
+    fn show_config(&self, guild_id: Id<GuildMarker>) -> Result<String> {
+        Ok(config_summary(&self.ctx.config.guild(guild_id)?))
+    }
+}
+
+/// Add the item to the list, remove it from it or clear it
+fn update_list<T: PartialEq>(list: &mut Vec<T>, action: &str, item: Option<T>) {
+    match (action, item) {
+        ("add", Some(item)) if !list.contains(&item) => list.push(item),
+        ("remove", Some(item)) => list.retain(|list_item| *list_item != item),
+        ("clear", _) => list.clear(),
+        _ => {}
+    }
+}
+
+/// The IDs as mentions, separated by commas
+fn mentions<T>(ids: &[Id<T>], prefix: &str) -> String {
+    ids.iter()
+        .map(|id| format!("<{prefix}{id}>"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The content listing the settings of the guild
+fn config_summary(config: &GuildConfig) -> String {
+    let on_off = |enabled| if enabled { "on" } else { "off" };
+
+    [
+        format!(
+            "**mentions that ping again:** {}",
+            match config.mention_policy {
+                MentionPolicy::None => "none",
+                MentionPolicy::Users => "only users",
+            }
+        ),
+        format!(
+            "**long messages:** {}",
+            match config.long_message_strategy {
+                LongMessageStrategy::Split => "split into multiple messages",
+                LongMessageStrategy::Embed => "put in an embed",
+                LongMessageStrategy::File => "attached as a text file",
+            }
+        ),
+        format!("**most messages to move at once:** {}", config.max_messages),
+        if config.undo_window_minutes == 0 {
+            "**undo window:** off".to_owned()
+        } else {
+            format!("**undo window:** {} minutes", config.undo_window_minutes)
+        },
+        format!(
+            "**prompt timeout:** {} minutes",
+            config.prompt_timeout_minutes
+        ),
+        match config.source_notice {
+            SourceNotice::Disabled => "**source notice:** off".to_owned(),
+            SourceNotice::Permanent => "**source notice:** on".to_owned(),
+            SourceNotice::AutoDelete => format!(
+                "**source notice:** deleted after {} minutes",
+                config.source_notice_delete_after_minutes
+            ),
+        },
+        format!("**reaction summary:** {}", on_off(config.reaction_summary)),
+        format!(
+            "**original timestamps:** {}",
+            on_off(config.original_timestamps)
+        ),
+        if config.allowed_channel_ids.is_empty() {
+            "**allowed channels:** all".to_owned()
+        } else {
+            format!(
+                "**allowed channels:** {}",
+                mentions(&config.allowed_channel_ids, "#")
+            )
+        },
+        if config.allowed_role_ids.is_empty() {
+            "**allowed roles:** everyone".to_owned()
+        } else {
+            format!(
+                "**allowed roles:** {}",
+                mentions(&config.allowed_role_ids, "@&")
+            )
+        },
+        if config.mover_role_ids.is_empty() {
+            "**roles that can move other people's messages:** none".to_owned()
+        } else {
+            format!(
+                "**roles that can move other people's messages:** {}",
+                mentions(&config.mover_role_ids, "@&")
+            )
+        },
+    ]
+    .into_iter()
+    .chain(
+        config
+            .channel_mover_role_ids
+            .iter()
+            .map(|(channel_id, role_ids)| {
+                format!(
+                    "**roles that can move other people's messages in <#{channel_id}>:** {}",
+                    if role_ids.is_empty() {
+                        "none".to_owned()
+                    } else {
+                        mentions(role_ids, "@&")
+                    }
+                )
+            }),
+    )
+    .chain(
+        config
+            .denied_role_id
+            .map(|role_id| format!("**role that can't move messages:** <@&{role_id}>")),
+    )
+    .collect::<Vec<_>>()
+    .join("\n")
+}
@@ -0,0 +1,155 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{
+        extract::{InteractionDataExt, InteractionExt},
+        DeferBehavior, DeferVisibility,
+    },
+    reply::Reply,
+};
+use twilight_model::{
+    application::interaction::Interaction,
+    channel::message::{
+        component::{ActionRow, Button, ButtonStyle},
+        Component,
+    },
+};
+
+use crate::{interaction::InteractionContext, job::Mode, CustomError};
+
+pub const CONFIRM_CUSTOM_ID: &str = "confirm_move";
+pub const CANCEL_CUSTOM_ID: &str = "cancel_move";
+
+/// How long interaction tokens can be used to edit the responses for
+const INTERACTION_TOKEN_LIFETIME: Duration = Duration::from_mins(15);
+
+/// A generous estimate of how long moving a message takes, including sending
+/// it, waiting before the next one and deleting the original one by one
+const MESSAGE_MOVE_DURATION: Duration = Duration::from_secs(2);
+
+impl InteractionContext<'_> {
+    /// When all the prompts of the command have to be responded to by, counted
+    /// from when the command was used so that the prompts together don't take
+    /// longer than the guild's prompt timeout
+    pub fn prompt_deadline(&self) -> Result<Instant> {
+        let guild_id = self.interaction.guild_id.ok()?;
+
+        Ok(self.received_at
+            + Duration::from_secs(self.ctx.config.guild(guild_id)?.prompt_timeout_minutes * 60))
+    }
+
+    /// The most messages that can be moved before the interaction token
+    /// expires, if the user responds to the prompts at the last moment
+    pub fn max_messages_in_time(&self) -> Result<usize> {
+        let time_left = (self.received_at + INTERACTION_TOKEN_LIFETIME)
+            .saturating_duration_since(self.prompt_deadline()?);
+
+        Ok(usize::try_from(
+            time_left.as_secs() / MESSAGE_MOVE_DURATION.as_secs(),
+        )?)
+    }
+
+    /// Return an error if the messages are more than the guild's limit or than
+    /// can be moved before the interaction token expires
+    pub fn check_message_count(&self, count: usize) -> Result<()> {
+        let max_messages = self
+            .ctx
+            .config
+            .guild(self.interaction.guild_id.ok()?)?
+            .max_messages;
+        if count > max_messages {
+            return Err(CustomError::TooManyMessages(max_messages).into());
+        }
+
+        let max_messages_in_time = self.max_messages_in_time()?;
+        if count > max_messages_in_time {
+            return Err(CustomError::TooManyMessagesInTime(max_messages_in_time).into());
+        }
+
+        Ok(())
+    }
+
+    /// A predicate accepting only the interactions of the user that ran the
+    /// command
+    pub fn is_invoker(&self) -> Result<impl Fn(&Interaction) -> bool + Send + Sync + 'static> {
+        let user_id = self.interaction.user().ok()?.id;
+
+        Ok(move |interaction: &Interaction| interaction.author_id() == Some(user_id))
+    }
+
+    /// Update the last reply to ask the user to confirm with buttons, returning
+    /// whether they confirmed
+    ///
+    /// Returns [`CustomError::PromptExpired`] if the user doesn't respond in
+    /// time
+    pub async fn wait_for_confirmation(&self, content: String, mode: Mode) -> Result<bool> {
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .update_last()
+                    .content(content)
+                    .component(Component::ActionRow(ActionRow {
+                        components: vec![
+                            Component::Button(Button {
+                                custom_id: Some(CONFIRM_CUSTOM_ID.to_owned()),
+                                disabled: false,
+                                emoji: None,
+                                label: Some(format!("{} them", mode.verb())),
+                                style: ButtonStyle::Success,
+                                url: None,
+                            }),
+                            Component::Button(Button {
+                                custom_id: Some(CANCEL_CUSTOM_ID.to_owned()),
+                                disabled: false,
+                                emoji: None,
+                                label: Some("cancel".to_owned()),
+                                style: ButtonStyle::Secondary,
+                                url: None,
+                            }),
+                        ],
+                    })),
+            )
+            .await?;
+
+        let confirmation_message = self
+            .ctx
+            .bot
+            .interaction_client()
+            .response(&self.interaction.token)
+            .await?
+            .model()
+            .await?;
+
+        let interaction = tokio::time::timeout_at(
+            self.prompt_deadline()?.into(),
+            self.ctx
+                .standby
+                .wait_for_component(confirmation_message.id, self.is_invoker()?),
+        )
+        .await
+        .map_err(|_| CustomError::PromptExpired)??;
+
+        self.ctx
+            .bot
+            .interaction_handle(&interaction)
+            .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
+            .await?;
+
+        let confirmed = interaction.data.ok()?.component().ok()?.custom_id == CONFIRM_CUSTOM_ID;
+        if !confirmed {
+            self.handle
+                .reply(
+                    Reply::new()
+                        .ephemeral()
+                        .update_last()
+                        .content(format!("okay, i didn't {} anything", mode.verb())),
+                )
+                .await?;
+        }
+
+        Ok(confirmed)
+    }
+}
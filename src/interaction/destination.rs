@@ -0,0 +1,91 @@
+use anyhow::Result;
+use twilight_model::{
+    channel::{Channel, ChannelType},
+    id::{
+        marker::{ChannelMarker, TagMarker},
+        Id,
+    },
+};
+
+use crate::{job::Mode, Context};
+
+/// Where to move or copy the messages to
+///
+/// New threads and posts are only created after the user confirms
+pub enum Destination {
+    Channel(Channel),
+    /// A new thread to create in the channel
+    NewThread {
+        parent: Channel,
+        name: String,
+    },
+    /// A new post to create in the forum channel
+    NewForumPost {
+        forum: Channel,
+        name: String,
+        tag_ids: Vec<Id<TagMarker>>,
+    },
+}
+
+impl Destination {
+    /// How to refer to the destination in messages
+    pub fn mention(&self) -> String {
+        match self {
+            Self::Channel(channel) => format!("<#{}>", channel.id),
+            Self::NewThread { parent, name } => {
+                format!("a new thread called **{name}** in <#{}>", parent.id)
+            }
+            Self::NewForumPost { forum, name, .. } => {
+                format!("a new post called **{name}** in <#{}>", forum.id)
+            }
+        }
+    }
+}
+
+impl Context {
+    /// The channel to send the messages to, creating the thread or the post if
+    /// the destination is a new one
+    pub async fn destination_channel(
+        &self,
+        destination: Destination,
+        source_channel_id: Id<ChannelMarker>,
+        mode: Mode,
+    ) -> Result<Channel> {
+        match destination {
+            Destination::Channel(channel) => Ok(channel),
+            Destination::NewThread { parent, name } => {
+                let kind = if parent.kind == ChannelType::GuildAnnouncement {
+                    ChannelType::AnnouncementThread
+                } else {
+                    ChannelType::PublicThread
+                };
+
+                Ok(self
+                    .bot
+                    .http
+                    .create_thread(parent.id, &name, kind)?
+                    .await?
+                    .model()
+                    .await?)
+            }
+            Destination::NewForumPost {
+                forum,
+                name,
+                tag_ids,
+            } => Ok(self
+                .bot
+                .http
+                .create_forum_thread(forum.id, &name)
+                .applied_tags(&tag_ids)
+                .message()
+                .content(&format!(
+                    "these messages are {} from <#{source_channel_id}>",
+                    mode.past_participle()
+                ))?
+                .await?
+                .model()
+                .await?
+                .channel),
+        }
+    }
+}
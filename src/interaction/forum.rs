@@ -0,0 +1,202 @@
+use anyhow::Result;
+use futures::StreamExt;
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{extract::InteractionDataExt, DeferBehavior, DeferVisibility},
+    reply::Reply,
+};
+use twilight_model::{
+    application::interaction::Interaction,
+    channel::{
+        message::{
+            component::{ActionRow, Button, ButtonStyle, SelectMenu, SelectMenuOption},
+            Component, ReactionType,
+        },
+        Channel, ChannelFlags, Message,
+    },
+    guild::Permissions,
+    id::{marker::TagMarker, Id},
+};
+
+use crate::{
+    interaction::{destination::Destination, InteractionContext},
+    permission::DESTINATION_BOT_PERMISSIONS,
+    CustomError,
+};
+
+pub const TAG_SELECT_CUSTOM_ID: &str = "forum_tags";
+pub const TAGS_DONE_CUSTOM_ID: &str = "forum_tags_done";
+
+/// The maximum number of tags a post can have
+const POST_TAG_COUNT_MAX: u8 = 5;
+
+impl InteractionContext<'_> {
+    /// Ask the user for the title of the post with a modal as the response to
+    /// the interaction and the tags of the post if the forum has any,
+    /// returning the new post in the forum as the destination
+    pub async fn new_forum_post_from_modal(
+        &self,
+        interaction: &Interaction,
+        forum: Channel,
+        first_message: &Message,
+    ) -> Result<Destination> {
+        let name = self
+            .wait_for_thread_name(interaction, first_message, "create a new post")
+            .await?;
+
+        let guild_id = self.interaction.guild_id.ok()?;
+        self.ctx.check_destination_allowed(&forum, guild_id)?;
+        self.ctx
+            .check_permissions(
+                &forum,
+                guild_id,
+                self.interaction.member.as_ref().ok()?,
+                Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+                DESTINATION_BOT_PERMISSIONS | Permissions::SEND_MESSAGES,
+            )
+            .await?;
+
+        let has_unmoderated_tags = forum
+            .available_tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|tag| !tag.moderated));
+        if !has_unmoderated_tags
+            && forum
+                .flags
+                .is_some_and(|flags| flags.contains(ChannelFlags::REQUIRE_TAG))
+        {
+            return Err(CustomError::ForumTagsModerated.into());
+        }
+
+        let tag_ids = if has_unmoderated_tags {
+            self.wait_for_tags(&forum).await?
+        } else {
+            vec![]
+        };
+
+        Ok(Destination::NewForumPost {
+            forum,
+            name,
+            tag_ids,
+        })
+    }
+
+    /// Update the last reply to ask the user to select the tags of the post,
+    /// returning the selected tags
+    async fn wait_for_tags(&self, forum: &Channel) -> Result<Vec<Id<TagMarker>>> {
+        let tags_required = forum
+            .flags
+            .is_some_and(|flags| flags.contains(ChannelFlags::REQUIRE_TAG));
+
+        let options = tag_options(forum);
+        let max_values = u8::try_from(options.len())
+            .unwrap_or(POST_TAG_COUNT_MAX)
+            .min(POST_TAG_COUNT_MAX);
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .update_last()
+                    .content("which tags should the post have?")
+                    .component(Component::ActionRow(ActionRow {
+                        components: vec![Component::SelectMenu(SelectMenu {
+                            custom_id: TAG_SELECT_CUSTOM_ID.to_owned(),
+                            disabled: false,
+                            max_values: Some(max_values),
+                            min_values: Some(u8::from(tags_required)),
+                            options,
+                            placeholder: Some("select the tags".to_owned()),
+                        })],
+                    }))
+                    .component(Component::ActionRow(ActionRow {
+                        components: vec![Component::Button(Button {
+                            custom_id: Some(TAGS_DONE_CUSTOM_ID.to_owned()),
+                            disabled: false,
+                            emoji: None,
+                            label: Some("done".to_owned()),
+                            style: ButtonStyle::Success,
+                            url: None,
+                        })],
+                    })),
+            )
+            .await?;
+
+        let tags_message = self
+            .ctx
+            .bot
+            .interaction_client()
+            .response(&self.interaction.token)
+            .await?
+            .model()
+            .await?;
+
+        let deadline = self.prompt_deadline()?;
+        let mut interactions = self
+            .ctx
+            .standby
+            .wait_for_component_stream(tags_message.id, self.is_invoker()?);
+
+        let mut tag_ids = vec![];
+        loop {
+            let interaction = tokio::time::timeout_at(deadline.into(), interactions.next())
+                .await
+                .map_err(|_| CustomError::PromptExpired)?
+                .ok()?;
+            let data = interaction.data.clone().ok()?.component().ok()?;
+
+            self.ctx
+                .bot
+                .interaction_handle(&interaction)
+                .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
+                .await?;
+
+            if data.custom_id == TAG_SELECT_CUSTOM_ID {
+                tag_ids = data
+                    .values
+                    .iter()
+                    .map(|id| id.parse())
+                    .collect::<Result<_, _>>()?;
+            } else if !tags_required || !tag_ids.is_empty() {
+                break;
+            }
+        }
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .update_last()
+                    .content("noted, doing some checks :face_with_monocle:"),
+            )
+            .await?;
+
+        Ok(tag_ids)
+    }
+}
+
+/// The options to select the tags of a post in the forum with, except the
+/// ones only moderators can apply
+fn tag_options(forum: &Channel) -> Vec<SelectMenuOption> {
+    forum
+        .available_tags
+        .iter()
+        .flatten()
+        .filter(|tag| !tag.moderated)
+        .map(|tag| SelectMenuOption {
+            default: false,
+            description: None,
+            emoji: match (tag.emoji_id, &tag.emoji_name) {
+                (Some(id), _) => Some(ReactionType::Custom {
+                    animated: false,
+                    id,
+                    name: None,
+                }),
+                (None, Some(name)) => Some(ReactionType::Unicode { name: name.clone() }),
+                (None, None) => None,
+            },
+            label: tag.name.clone(),
+            value: tag.id.to_string(),
+        })
+        .collect()
+}
@@ -1,12 +1,28 @@
 use anyhow::Result;
-use sparkle_convenience::{error::IntoError, interaction::extract::InteractionDataExt};
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{extract::InteractionDataExt, DeferBehavior, DeferVisibility},
+};
 use twilight_model::{channel::Message, guild::Permissions};
 
-use crate::{interaction::InteractionContext, CustomError, REQUIRED_PERMISSIONS};
+use crate::{
+    interaction::InteractionContext, permission::SOURCE_MEMBER_PERMISSIONS, CustomError,
+    REQUIRED_PERMISSIONS,
+};
 
 impl InteractionContext<'_> {
-    pub fn handle_message_command(&self) -> Result<Message> {
-        self.handle.check_permissions(REQUIRED_PERMISSIONS)?;
+    /// The message the command is used on and whether the user can move other
+    /// people's messages in its channel
+    ///
+    /// The interaction is deferred first so that the checks don't make it
+    /// expire
+    pub async fn handle_message_command(&self) -> Result<(Message, bool)> {
+        self.handle
+            .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Followup)
+            .await?;
+
+        self.check_role_allowed()?;
+        let can_move_others = self.check_source_permissions().await?;
 
         let message = self
             .interaction
@@ -22,18 +38,83 @@ impl InteractionContext<'_> {
             .next()
             .ok()?
             .1;
+        self.check_can_move(&message, can_move_others)?;
+
+        Ok((message, can_move_others))
+    }
+
+    /// Return an error if the user or the bot is missing permissions in the
+    /// channel the command is used in, returning whether the user can move
+    /// other people's messages in it
+    ///
+    /// Users can move other people's messages if they have **Manage Messages**
+    /// permissions or one of the roles the guild lets move them in the channel
+    ///
+    /// The permissions in the interaction are used, which already account for
+    /// the channel's permission overwrites, the channel is only fetched to
+    /// check if it's a private thread
+    pub async fn check_source_permissions(&self) -> Result<bool> {
+        let channel = self
+            .ctx
+            .bot
+            .http
+            .channel(self.interaction.channel_id.ok()?)
+            .await?
+            .model()
+            .await?;
+        let member = self.interaction.member.as_ref().ok()?;
+        let member_permissions = member.permissions.ok()?;
+
+        self.ctx
+            .check_bot_permissions(
+                &channel,
+                self.interaction.app_permissions.ok()?,
+                REQUIRED_PERMISSIONS,
+            )
+            .await?;
+        self.ctx
+            .check_member_permissions(
+                &channel,
+                member.user.as_ref().ok()?.id,
+                member_permissions,
+                SOURCE_MEMBER_PERMISSIONS,
+            )
+            .await?;
+
+        Ok(member_permissions.contains(Permissions::MANAGE_MESSAGES)
+            || self
+                .ctx
+                .config
+                .guild(self.interaction.guild_id.ok()?)?
+                .is_mover(&member.roles, &channel))
+    }
+
+    /// Return an error if the user has the role that can't move messages, or if
+    /// the guild only lets some roles move messages and the user doesn't have
+    /// any of them, unless they can manage the guild
+    pub fn check_role_allowed(&self) -> Result<()> {
         let member = self.interaction.member.as_ref().ok()?;
-        let user = member.user.as_ref().ok()?;
+        let config = self.ctx.config.guild(self.interaction.guild_id.ok()?)?;
 
-        if message.author.id != user.id
-            && !member
-                .permissions
-                .ok()?
-                .contains(Permissions::MANAGE_MESSAGES)
+        if config.is_denied(&member.roles)
+            || (!member.permissions.ok()?.contains(Permissions::MANAGE_GUILD)
+                && !config.is_role_allowed(&member.roles))
         {
+            return Err(CustomError::RoleNotAllowed.into());
+        }
+
+        Ok(())
+    }
+
+    /// Return an error if the message isn't the user's own and they can't move
+    /// other people's messages
+    pub fn check_can_move(&self, message: &Message, can_move_others: bool) -> Result<()> {
+        let user = self.interaction.member.as_ref().ok()?.user.as_ref().ok()?;
+
+        if message.author.id != user.id && !can_move_others {
             return Err(CustomError::ManageMessagesPermissionsMissing.into());
         }
 
-        Ok(message)
+        Ok(())
     }
 }
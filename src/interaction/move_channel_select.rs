@@ -1,4 +1,5 @@
 use anyhow::Result;
+use futures::StreamExt;
 use sparkle_convenience::{
     error::IntoError,
     interaction::{extract::InteractionDataExt, DeferBehavior, DeferVisibility},
@@ -6,49 +7,189 @@ use sparkle_convenience::{
 };
 use twilight_model::{
     application::interaction::Interaction,
-    channel::{Channel, ChannelType},
-    guild::Permissions,
-    id::{marker::ChannelMarker, Id},
+    channel::{
+        message::component::{Button, ButtonStyle},
+        Channel, ChannelType, Message,
+    },
+    guild::{PartialMember, Permissions},
+    id::{
+        marker::{ChannelMarker, GuildMarker, MessageMarker, UserMarker},
+        Id,
+    },
 };
-use twilight_util::permission_calculator::PermissionCalculator;
 
 use crate::{
-    interaction::{channel_select_menu::ChannelSelectMenu, InteractionContext},
+    interaction::{
+        channel_select_menu::{ChannelSelectMenu, SelectMenu, UserSelectMenu},
+        destination::Destination,
+        new_thread::THREAD_PARENT_CHANNEL_TYPES,
+        InteractionContext,
+    },
+    job::Mode,
+    permission::DESTINATION_BOT_PERMISSIONS,
     Context, CustomError,
 };
 
 pub const CUSTOM_ID: &str = "move_channel";
+pub const AUTHOR_SELECT_CUSTOM_ID: &str = "move_authors";
+pub const CANCEL_CUSTOM_ID: &str = "cancel_channel_select";
+pub const NEW_THREAD_HERE_CUSTOM_ID: &str = "new_thread_here";
+pub const NEW_THREAD_CHANNEL_CUSTOM_ID: &str = "new_thread_channel";
+
+/// The types of channels messages can be moved to
+pub const MOVE_CHANNEL_TYPES: [ChannelType; 6] = [
+    ChannelType::GuildText,
+    ChannelType::GuildAnnouncement,
+    ChannelType::GuildForum,
+    ChannelType::AnnouncementThread,
+    ChannelType::PublicThread,
+    ChannelType::PrivateThread,
+];
+
+impl InteractionContext<'_> {
+    /// Wait for the user to select the channel to move or copy the messages to
+    ///
+    /// The user can also create a new thread in the channel of `first_message`
+    /// or in another channel to move the messages to, selecting a forum
+    /// channel creates a new post in it
+    ///
+    /// If `select_authors` is true, the user can also select the authors of the
+    /// messages, if they don't select any, the returned list is empty
+    ///
+    /// Returns `None` if the user cancels or doesn't select a channel in time,
+    /// the prompt is edited to tell them in both cases
+    pub async fn wait_for_channel_select_interaction(
+        &self,
+        first_message: &Message,
+        select_authors: bool,
+        mode: Mode,
+    ) -> Result<Option<(Destination, Vec<Id<UserMarker>>)>> {
+        let verb = mode.verb();
 
-impl<'a> InteractionContext<'a> {
-    pub async fn wait_for_channel_select_interaction(&self) -> Result<Channel> {
-        self.handle
-            .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
-            .await?;
         let channel_select_message = self
-            .followup_with_channel_select_menu(
-                "where do you want to move the message?".to_owned(),
+            .followup_with_select_menus(
+                if select_authors {
+                    format!(
+                        "where do you want to {verb} the messages? if you only want to {verb} the \
+                         messages of some users, select them before the channel"
+                    )
+                } else {
+                    format!("where do you want to {verb} the message?")
+                },
                 DeferVisibility::Ephemeral,
-                ChannelSelectMenu::new(
-                    CUSTOM_ID.to_owned(),
-                    vec![
-                        ChannelType::GuildText,
-                        ChannelType::GuildAnnouncement,
-                        ChannelType::AnnouncementThread,
-                        ChannelType::PublicThread,
-                        ChannelType::PrivateThread,
-                    ],
-                ),
+                channel_select_menus(select_authors, verb),
+                channel_select_buttons(),
             )
             .await?
             .model()
             .await?;
 
-        let interaction = self
+        let deadline = self.prompt_deadline()?;
+        let mut interactions = self
             .ctx
             .standby
-            .wait_for_component(channel_select_message.id, |_: &Interaction| true)
+            .wait_for_component_stream(channel_select_message.id, self.is_invoker()?);
+
+        let mut author_ids = vec![];
+        let (interaction, custom_id) = loop {
+            let Ok(interaction) =
+                tokio::time::timeout_at(deadline.into(), interactions.next()).await
+            else {
+                self.close_prompt(
+                    channel_select_message.id,
+                    &CustomError::PromptExpired.to_string(),
+                )
+                .await?;
+                return Ok(None);
+            };
+            let interaction = interaction.ok()?;
+            let data = interaction.data.clone().ok()?.component().ok()?;
+
+            if data.custom_id == CANCEL_CUSTOM_ID {
+                self.ctx
+                    .bot
+                    .interaction_handle(&interaction)
+                    .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
+                    .await?;
+                self.close_prompt(
+                    channel_select_message.id,
+                    &format!("okay, i didn't {verb} anything"),
+                )
+                .await?;
+
+                return Ok(None);
+            }
+            if data.custom_id != AUTHOR_SELECT_CUSTOM_ID {
+                break (interaction, data.custom_id);
+            }
+
+            author_ids = data
+                .values
+                .iter()
+                .map(|id| id.parse())
+                .collect::<Result<_, _>>()?;
+            self.ctx
+                .bot
+                .interaction_handle(&interaction)
+                .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
+                .await?;
+        };
+
+        let destination = match custom_id.as_str() {
+            NEW_THREAD_HERE_CUSTOM_ID => {
+                Box::pin(self.new_thread_from_modal(
+                    &interaction,
+                    first_message.channel_id,
+                    first_message,
+                ))
+                .await?
+            }
+            NEW_THREAD_CHANNEL_CUSTOM_ID => {
+                let channel_id = selected_channel_id(&interaction)?;
+                Box::pin(self.new_thread_from_modal(&interaction, channel_id, first_message))
+                    .await?
+            }
+            _ => Box::pin(self.selected_destination(&interaction, first_message)).await?,
+        };
+
+        Ok(Some((destination, author_ids)))
+    }
+
+    /// Replace the prompt's content and remove its components so that it can't
+    /// be used anymore
+    async fn close_prompt(&self, message_id: Id<MessageMarker>, content: &str) -> Result<()> {
+        self.ctx
+            .bot
+            .interaction_client()
+            .update_followup(&self.interaction.token, message_id)
+            .content(Some(content))?
+            .components(Some(&[]))?
             .await?;
 
+        Ok(())
+    }
+
+    /// The destination for the channel the user selected, asking for the post
+    /// to create if it's a forum channel
+    async fn selected_destination(
+        &self,
+        interaction: &Interaction,
+        first_message: &Message,
+    ) -> Result<Destination> {
+        let channel = self
+            .ctx
+            .bot
+            .http
+            .channel(selected_channel_id(interaction)?)
+            .await?
+            .model()
+            .await?;
+
+        if channel.kind == ChannelType::GuildForum {
+            return Box::pin(self.new_forum_post_from_modal(interaction, channel, first_message))
+                .await;
+        }
+
         self.handle
             .reply(
                 Reply::new()
@@ -58,80 +199,126 @@ impl<'a> InteractionContext<'a> {
             )
             .await?;
 
-        self.ctx.move_channel(interaction).await
+        Ok(Destination::Channel(
+            self.ctx.move_channel(interaction.clone()).await?,
+        ))
+    }
+}
+
+/// The select menus for the channel to move to, the authors of the messages if
+/// `select_authors` is true and the channel to create a new thread in
+fn channel_select_menus(select_authors: bool, verb: &str) -> Vec<SelectMenu> {
+    let mut menus = vec![SelectMenu::Channel(ChannelSelectMenu::new(
+        CUSTOM_ID.to_owned(),
+        format!("{verb} to this channel"),
+        MOVE_CHANNEL_TYPES.to_vec(),
+    ))];
+    if select_authors {
+        menus.push(SelectMenu::User(UserSelectMenu::new(
+            AUTHOR_SELECT_CUSTOM_ID.to_owned(),
+            format!("only {verb} the messages of these users"),
+        )));
     }
+    menus.push(SelectMenu::Channel(ChannelSelectMenu::new(
+        NEW_THREAD_CHANNEL_CUSTOM_ID.to_owned(),
+        format!("{verb} to a new thread in this channel"),
+        THREAD_PARENT_CHANNEL_TYPES.to_vec(),
+    )));
+
+    menus
+}
+
+/// The buttons to create a new thread in the current channel and to cancel
+fn channel_select_buttons() -> Vec<Button> {
+    vec![
+        Button {
+            custom_id: Some(NEW_THREAD_HERE_CUSTOM_ID.to_owned()),
+            disabled: false,
+            emoji: None,
+            label: Some("create a new thread here".to_owned()),
+            style: ButtonStyle::Primary,
+            url: None,
+        },
+        Button {
+            custom_id: Some(CANCEL_CUSTOM_ID.to_owned()),
+            disabled: false,
+            emoji: None,
+            label: Some("cancel".to_owned()),
+            style: ButtonStyle::Secondary,
+            url: None,
+        },
+    ]
+}
+
+/// The ID of the channel selected in the channel select interaction
+fn selected_channel_id(interaction: &Interaction) -> Result<Id<ChannelMarker>> {
+    Ok(interaction
+        .data
+        .clone()
+        .ok()?
+        .component()
+        .ok()?
+        .values
+        .into_iter()
+        .next()
+        .ok()?
+        .parse()?)
 }
 
 impl Context {
     async fn move_channel(&self, interaction: Interaction) -> Result<Channel> {
-        let channel_id = interaction
-            .data
-            .clone()
-            .ok()?
-            .component()
-            .ok()?
-            .values
-            .into_iter()
-            .next()
-            .ok()?
-            .parse::<Id<ChannelMarker>>()?;
-        let member = interaction.member.as_ref().ok()?;
-
-        let guild = self
-            .bot
-            .http
-            .guild(interaction.guild_id.ok()?)
-            .await?
-            .model()
-            .await?;
+        self.check_move_channel(
+            selected_channel_id(&interaction)?,
+            interaction.guild_id.ok()?,
+            interaction.member.as_ref().ok()?,
+        )
+        .await
+    }
+
+    /// Return the channel if the member can send messages in it and the bot
+    /// can send webhook messages in it
+    pub async fn check_move_channel(
+        &self,
+        channel_id: Id<ChannelMarker>,
+        guild_id: Id<GuildMarker>,
+        member: &PartialMember,
+    ) -> Result<Channel> {
         let channel = self.bot.http.channel(channel_id).await?.model().await?;
-        let permission_overwrites = if channel.kind.is_thread() {
-            self.bot
-                .http
-                .channel(channel.parent_id.ok()?)
-                .await?
-                .model()
-                .await?
-                .permission_overwrites
-                .ok()?
-        } else {
-            channel.permission_overwrites.clone().ok()?
-        };
+        self.check_destination_allowed(&channel, guild_id)?;
 
-        let everyone_role = guild
-            .roles
-            .iter()
-            .find_map(|role| (role.id.cast() == guild.id).then_some(role.permissions))
-            .ok()?;
-
-        let member_roles = guild
-            .roles
-            .iter()
-            .filter_map(|role| {
-                member
-                    .roles
-                    .contains(&role.id)
-                    .then_some((role.id, role.permissions))
-            })
-            .collect::<Vec<_>>();
-
-        let permissions = PermissionCalculator::new(
-            guild.id,
-            member.user.as_ref().ok()?.id,
-            everyone_role,
-            &member_roles,
+        let member_permissions = Permissions::VIEW_CHANNEL
+            | if channel.kind.is_thread() {
+                Permissions::SEND_MESSAGES_IN_THREADS
+            } else {
+                Permissions::SEND_MESSAGES
+            };
+        let mut bot_permissions = DESTINATION_BOT_PERMISSIONS;
+        if channel.kind == ChannelType::GuildForum {
+            bot_permissions |= Permissions::SEND_MESSAGES;
+        }
+        self.check_permissions(
+            &channel,
+            guild_id,
+            member,
+            member_permissions,
+            bot_permissions,
         )
-        .in_channel(channel.kind, &permission_overwrites);
+        .await?;
 
-        let required_permissions = if channel.kind.is_thread() {
-            Permissions::SEND_MESSAGES_IN_THREADS
-        } else {
-            Permissions::SEND_MESSAGES
-        };
-        if !permissions.contains(required_permissions) {
-            return Err(CustomError::SendMessagesPermissionMissing.into());
+        Ok(channel)
+    }
+
+    /// Return an error if the guild doesn't let messages be moved to the
+    /// channel
+    pub fn check_destination_allowed(
+        &self,
+        channel: &Channel,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<()> {
+        if !self.config.guild(guild_id)?.is_destination_allowed(channel) {
+            return Err(CustomError::DestinationNotAllowed(channel.id).into());
         }
 
-        Ok(channel)
+        Ok(())
     }
 }
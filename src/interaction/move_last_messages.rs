@@ -0,0 +1,206 @@
+use anyhow::Result;
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{extract::InteractionDataExt, DeferBehavior, DeferVisibility},
+};
+use twilight_model::{
+    application::command::{Command, CommandType},
+    channel::{ChannelFlags, ChannelType, Message},
+    id::{
+        marker::{ChannelMarker, MessageMarker, UserMarker},
+        Id,
+    },
+    util::Timestamp,
+};
+use twilight_util::builder::command::{
+    ChannelBuilder, CommandBuilder, IntegerBuilder, StringBuilder, UserBuilder,
+};
+
+use crate::{
+    interaction::{
+        command_options::{channel_option, integer_option, string_option, user_option},
+        config::MAX_MESSAGES_LIMIT,
+        destination::Destination,
+        move_channel_select::MOVE_CHANNEL_TYPES,
+        move_message_and_below::{MESSAGES_PAGE_LIMIT, SCAN_LIMIT},
+        new_thread::default_thread_name,
+        InteractionContext,
+    },
+    job::Mode,
+    CustomError,
+};
+
+pub const NAME: &str = "move";
+
+/// The first second of 2015 in milliseconds, which message IDs are relative to
+const DISCORD_EPOCH_MILLIS: i64 = 1_420_070_400_000;
+
+pub fn command() -> Command {
+    CommandBuilder::new(
+        NAME,
+        "move the last messages in this channel to another channel",
+        CommandType::ChatInput,
+    )
+    .dm_permission(false)
+    .option(
+        IntegerBuilder::new("count", "how many messages to move")
+            .required(true)
+            .min_value(1)
+            .max_value(MAX_MESSAGES_LIMIT),
+    )
+    .option(
+        ChannelBuilder::new("channel", "the channel to move the messages to")
+            .required(true)
+            .channel_types(MOVE_CHANNEL_TYPES),
+    )
+    .option(UserBuilder::new(
+        "author",
+        "only move the messages of this user",
+    ))
+    .option(StringBuilder::new(
+        "since",
+        "only move messages sent after this time, like 1676917800 or 2023-02-20T18:30:00Z",
+    ))
+    .build()
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_move_command(self) -> Result<()> {
+        self.check_role_allowed()?;
+
+        let guild_id = self.interaction.guild_id.ok()?;
+        let options = self.interaction.data.clone().ok()?.command().ok()?.options;
+
+        let count = usize::try_from(integer_option(&options, "count").ok()?)?;
+        self.check_message_count(count)?;
+
+        let since = string_option(&options, "since")
+            .map(|since| parse_since(&since))
+            .transpose()?;
+
+        self.handle
+            .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Followup)
+            .await?;
+
+        let can_move_others = self.check_source_permissions().await?;
+
+        let channel = self
+            .ctx
+            .check_move_channel(
+                channel_option(&options, "channel").ok()?,
+                guild_id,
+                self.interaction.member.as_ref().ok()?,
+            )
+            .await?;
+
+        let messages = self
+            .last_messages(
+                self.interaction.channel_id.ok()?,
+                count,
+                user_option(&options, "author"),
+                since,
+            )
+            .await?;
+        let (messages, rejected) = self.partition_movable(messages, &[], can_move_others)?;
+
+        let destination = if channel.kind == ChannelType::GuildForum {
+            if channel
+                .flags
+                .is_some_and(|flags| flags.contains(ChannelFlags::REQUIRE_TAG))
+            {
+                return Err(CustomError::ForumTagsRequired.into());
+            }
+
+            Destination::NewForumPost {
+                name: default_thread_name(&messages[0]),
+                forum: channel,
+                tag_ids: vec![],
+            }
+        } else {
+            Destination::Channel(channel)
+        };
+
+        self.move_messages(&messages, &rejected, destination, guild_id, Mode::Move)
+            .await
+    }
+
+    /// The last messages in the channel, oldest first
+    ///
+    /// If `author_id` is given, only the messages sent by that author are
+    /// returned, if `since` is given, only the messages after that message ID
+    /// are returned
+    async fn last_messages(
+        &self,
+        channel_id: Id<ChannelMarker>,
+        count: usize,
+        author_id: Option<Id<UserMarker>>,
+        since: Option<Id<MessageMarker>>,
+    ) -> Result<Vec<Message>> {
+        let mut messages = vec![];
+        let mut before = None;
+        let mut scanned_count = 0;
+
+        while messages.len() < count && scanned_count < SCAN_LIMIT {
+            let request = self.ctx.bot.http.channel_messages(channel_id);
+            let page = match before {
+                Some(before) => request.before(before).limit(MESSAGES_PAGE_LIMIT)?.await?,
+                None => request.limit(MESSAGES_PAGE_LIMIT)?.await?,
+            }
+            .models()
+            .await?;
+
+            scanned_count += page.len();
+            let mut is_last_page = page.len() < usize::from(MESSAGES_PAGE_LIMIT);
+            before = page.last().map(|message| message.id);
+
+            for message in page {
+                if since.is_some_and(|since| message.id <= since) {
+                    is_last_page = true;
+                    break;
+                }
+                if author_id.is_none_or(|author_id| message.author.id == author_id) {
+                    messages.push(message);
+                }
+                if messages.len() == count {
+                    break;
+                }
+            }
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        if messages.is_empty() {
+            return Err(CustomError::NoMessagesFound.into());
+        }
+
+        messages.reverse();
+        Ok(messages)
+    }
+}
+
+/// Parse a unix timestamp, a Discord timestamp like `<t:1676917800:f>` or an
+/// ISO 8601 timestamp into the ID a message sent at that time would have
+fn parse_since(since: &str) -> Result<Id<MessageMarker>> {
+    let since = since.trim();
+    let unix_secs = since
+        .trim_start_matches("<t:")
+        .trim_end_matches('>')
+        .split(':')
+        .next()
+        .and_then(|secs| secs.parse().ok());
+
+    let timestamp = unix_secs
+        .map_or_else(|| Timestamp::parse(since), Timestamp::from_secs)
+        .map_err(|_| CustomError::InvalidTimestamp)?;
+
+    let millis = u64::try_from(timestamp.as_micros() / 1000 - DISCORD_EPOCH_MILLIS)
+        .map_err(|_| CustomError::InvalidTimestamp)?;
+    // the milliseconds are the top 42 bits of the ID
+    if millis >= 1 << 42 {
+        return Err(CustomError::InvalidTimestamp.into());
+    }
+
+    Ok(Id::new_checked(millis << 22).ok_or(CustomError::InvalidTimestamp)?)
+}
@@ -1,11 +1,18 @@
+use std::slice;
+
 use anyhow::Result;
 use sparkle_convenience::reply::Reply;
 use twilight_model::application::command::{Command, CommandType};
 use twilight_util::builder::command::CommandBuilder;
 
-use crate::{interaction::InteractionContext, message};
+use crate::{
+    interaction::{preview::preview, InteractionContext},
+    job::Mode,
+    CustomError,
+};
 
 pub const NAME: &str = "move message";
+pub const COPY_NAME: &str = "copy message";
 
 pub fn command() -> Command {
     CommandBuilder::new(NAME, "", CommandType::Message)
@@ -13,32 +20,36 @@ pub fn command() -> Command {
         .build()
 }
 
+pub fn copy_command() -> Command {
+    CommandBuilder::new(COPY_NAME, "", CommandType::Message)
+        .dm_permission(false)
+        .build()
+}
+
 impl InteractionContext<'_> {
-    pub async fn handle_move_message_command(self) -> Result<()> {
-        let message = self.handle_message_command()?;
-        message::check(&message)?;
+    pub async fn handle_move_message_command(self, mode: Mode) -> Result<()> {
+        let (message, can_move_others) = self.handle_message_command().await?;
 
         let message_id = message.id;
         let message_channel_id = message.channel_id;
 
-        let channel = self.wait_for_channel_select_interaction().await?;
+        let Some((destination, _)) =
+            Box::pin(self.wait_for_channel_select_interaction(&message, false, mode)).await?
+        else {
+            return Ok(());
+        };
 
-        self.handle
-            .reply(
-                Reply::new()
-                    .ephemeral()
-                    .update_last()
-                    .content("starting up the bike :motor_scooter:"),
-            )
-            .await?;
-
-        self.ctx
-            .execute_webhook_as_member(&message, &channel)
-            .await?;
-        self.ctx
-            .bot
-            .http
-            .delete_message(message_channel_id, message_id)
+        let (messages, rejected) = self.partition_movable(vec![message], &[], can_move_others)?;
+        if !self
+            .wait_for_confirmation(preview(&messages, &rejected, &destination, mode), mode)
+            .await?
+        {
+            return Ok(());
+        }
+        let message = &messages[0];
+        let channel = self
+            .ctx
+            .destination_channel(destination, message_channel_id, mode)
             .await?;
 
         self.handle
@@ -46,10 +57,40 @@ impl InteractionContext<'_> {
                 Reply::new()
                     .ephemeral()
                     .update_last()
-                    .content("done :incoming_envelope:"),
+                    .content("starting up the bike :motor_scooter:"),
             )
             .await?;
 
-        Ok(())
+        let mut copies = vec![];
+        if let Err(err) = self
+            .ctx
+            .execute_webhook_as_member(message, &channel, None, &mut copies)
+            .await
+        {
+            return Err(self
+                .roll_back(err, &[(message_id, copies)], &channel)
+                .await
+                .into());
+        }
+        if mode == Mode::Move {
+            if let Err(err) = self
+                .ctx
+                .bot
+                .http
+                .delete_message(message_channel_id, message_id)
+                .await
+            {
+                self.ctx.bot.log(&err).await;
+                return Err(CustomError::DeletingOriginalsFailed(message_channel_id).into());
+            }
+        }
+
+        self.finish_move(
+            slice::from_ref(message),
+            channel.id,
+            mode,
+            vec![(message_id, copies)],
+        )
+        .await
     }
 }
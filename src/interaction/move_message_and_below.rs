@@ -1,13 +1,36 @@
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
 
 use anyhow::Result;
 use sparkle_convenience::{error::IntoError, reply::Reply};
-use twilight_model::application::command::{Command, CommandType};
+use twilight_model::{
+    application::command::{Command, CommandType},
+    channel::{Channel, Message},
+    id::{
+        marker::{GuildMarker, MessageMarker, UserMarker},
+        Id,
+    },
+};
 use twilight_util::builder::command::CommandBuilder;
 
-use crate::{interaction::InteractionContext, message};
+use crate::{
+    interaction::{
+        destination::Destination,
+        preview::{preview, RejectedMessage},
+        InteractionContext,
+    },
+    job::{Job, Mode},
+    message::replied_copy,
+    CustomError,
+};
 
 pub const NAME: &str = "move this message and below";
+pub const COPY_NAME: &str = "copy this message and below";
+
+/// The maximum number of messages Discord returns in one request
+pub const MESSAGES_PAGE_LIMIT: u16 = 100;
+
+/// The most messages to look through to find the messages to move
+pub const SCAN_LIMIT: usize = 1000;
 
 pub fn command() -> Command {
     CommandBuilder::new(NAME, "", CommandType::Message)
@@ -15,29 +38,53 @@ pub fn command() -> Command {
         .build()
 }
 
+pub fn copy_command() -> Command {
+    CommandBuilder::new(COPY_NAME, "", CommandType::Message)
+        .dm_permission(false)
+        .build()
+}
+
 impl InteractionContext<'_> {
-    pub async fn handle_move_message_and_below_command(self) -> Result<()> {
+    pub async fn handle_move_message_and_below_command(self, mode: Mode) -> Result<()> {
         let guild_id = self.interaction.guild_id.ok()?;
 
-        let mut messages = vec![self.handle_message_command()?];
+        let (message, can_move_others) = self.handle_message_command().await?;
+        let mut messages = vec![message];
 
-        let channel = self.wait_for_channel_select_interaction().await?;
+        let Some((destination, author_ids)) =
+            Box::pin(self.wait_for_channel_select_interaction(&messages[0], true, mode)).await?
+        else {
+            return Ok(());
+        };
 
-        let mut channel_messages = self
-            .ctx
-            .bot
-            .http
-            .channel_messages(messages[0].channel_id)
-            .after(messages[0].id)
-            .await?
-            .models()
+        self.append_messages_below(&mut messages, None, &author_ids)
             .await?;
-        channel_messages.reverse();
-        messages.append(&mut channel_messages);
+        let (messages, rejected) =
+            self.partition_movable(messages, &author_ids, can_move_others)?;
+
+        self.move_messages(&messages, &rejected, destination, guild_id, mode)
+            .await
+    }
 
-        for message in &messages {
-            message::check(message)?;
+    /// Move or copy the messages after the user confirms the preview
+    pub async fn move_messages(
+        &self,
+        messages: &[Message],
+        rejected: &[RejectedMessage],
+        destination: Destination,
+        guild_id: Id<GuildMarker>,
+        mode: Mode,
+    ) -> Result<()> {
+        if !self
+            .wait_for_confirmation(preview(messages, rejected, &destination, mode), mode)
+            .await?
+        {
+            return Ok(());
         }
+        let channel = &self
+            .ctx
+            .destination_channel(destination, messages[0].channel_id, mode)
+            .await?;
 
         let reply_content = match messages.len() {
             0..=10 => "starting up the car :red_car:",
@@ -55,6 +102,55 @@ impl InteractionContext<'_> {
             )
             .await?;
 
+        self.ctx.jobs.insert(
+            self.interaction.id,
+            Job {
+                guild_id,
+                source_channel_id: messages[0].channel_id,
+                destination_channel_id: channel.id,
+                mode,
+                message_ids: messages.iter().map(|message| message.id).collect(),
+                copied_count: 0,
+                interaction_token: self.interaction.token.clone(),
+            },
+        )?;
+
+        let mut copies = vec![];
+        let copy_res = self
+            .copy_messages(messages, channel, guild_id, &mut copies)
+            .await;
+        if let Err(err) = copy_res {
+            self.ctx.jobs.remove(self.interaction.id)?;
+            return Err(self.roll_back(err, &copies, channel).await.into());
+        }
+
+        let delete_res = if mode == Mode::Move {
+            self.ctx.delete_messages(messages, guild_id).await
+        } else {
+            Ok(())
+        };
+        self.ctx.jobs.remove(self.interaction.id)?;
+        if let Err(err) = delete_res {
+            self.ctx.bot.log(&err).await;
+            return Err(CustomError::DeletingOriginalsFailed(messages[0].channel_id).into());
+        }
+
+        self.finish_move(messages, channel.id, mode, copies).await?;
+
+        println!("{guild_id} done");
+
+        Ok(())
+    }
+
+    /// Send the copies of the messages to the channel, pushing the copies of
+    /// each message to `copies` and saving the progress as they're sent
+    async fn copy_messages(
+        &self,
+        messages: &[Message],
+        channel: &Channel,
+        guild_id: Id<GuildMarker>,
+        copies: &mut Vec<(Id<MessageMarker>, Vec<Message>)>,
+    ) -> Result<()> {
         for (idx, message) in messages.iter().enumerate() {
             if (idx + 1) % 10 == 0 {
                 println!(
@@ -64,59 +160,99 @@ impl InteractionContext<'_> {
                 );
             }
 
-            self.ctx
-                .execute_webhook_as_member(message, &channel)
-                .await?;
+            let mut message_copies = vec![];
+            let execute_res = self
+                .ctx
+                .execute_webhook_as_member(
+                    message,
+                    channel,
+                    replied_copy(message, copies),
+                    &mut message_copies,
+                )
+                .await;
+            copies.push((message.id, message_copies));
+            execute_res?;
+            self.ctx.jobs.increment_copied_count(self.interaction.id)?;
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
 
-        if (SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
-            - u64::try_from(messages[0].timestamp.as_secs())?)
-            > 2 * 7 * 24 * 60 * 60
-            || messages.len() == 1
-        {
-            for (idx, message) in messages.iter().enumerate() {
-                if (idx + 1) % 10 == 0 {
-                    println!(
-                        "deleting messages in {guild_id}: {}/{}",
-                        idx + 1,
-                        messages.len()
-                    );
-                }
-
-                self.ctx
-                    .bot
-                    .http
-                    .delete_message(message.channel_id, message.id)
-                    .await?;
-
-                tokio::time::sleep(Duration::from_secs(1)).await;
-            }
-        } else {
-            self.ctx
+        Ok(())
+    }
+
+    /// Log the error that stopped copying the messages and delete the copies
+    /// sent so far, returning the error that tells the user the state the
+    /// channels are left in
+    pub async fn roll_back(
+        &self,
+        err: anyhow::Error,
+        copies: &[(Id<MessageMarker>, Vec<Message>)],
+        channel: &Channel,
+    ) -> CustomError {
+        self.ctx.bot.log(&err).await;
+
+        let copies = copies
+            .iter()
+            .flat_map(|(_, copies)| copies.clone())
+            .collect::<Vec<_>>();
+        if copies.is_empty() {
+            return CustomError::MoveRolledBack;
+        }
+
+        if let Err(err) = self.ctx.delete_copies(&copies).await {
+            self.ctx.bot.log(&err).await;
+            return CustomError::RollbackFailed(channel.id);
+        }
+
+        CustomError::MoveRolledBack
+    }
+
+    /// Append the messages sent after the last message until the given message
+    /// or the latest one
+    ///
+    /// If `author_ids` isn't empty, only the messages sent by these users count
+    /// towards the guild's limit of messages to move
+    pub async fn append_messages_below(
+        &self,
+        messages: &mut Vec<Message>,
+        until: Option<Id<MessageMarker>>,
+        author_ids: &[Id<UserMarker>],
+    ) -> Result<()> {
+        loop {
+            let last_message = messages.last().ok()?;
+            let mut channel_messages = self
+                .ctx
                 .bot
                 .http
-                .delete_messages(
-                    messages[0].channel_id,
-                    &messages
-                        .iter()
-                        .map(|message| message.id)
-                        .collect::<Vec<_>>(),
-                )?
+                .channel_messages(last_message.channel_id)
+                .after(last_message.id)
+                .limit(MESSAGES_PAGE_LIMIT)?
+                .await?
+                .models()
                 .await?;
-        }
+            let mut is_last_page = channel_messages.len() < usize::from(MESSAGES_PAGE_LIMIT);
 
-        self.handle
-            .reply(
-                Reply::new()
-                    .ephemeral()
-                    .update_last()
-                    .content("done :incoming_envelope:"),
-            )
-            .await?;
+            if let Some(until) = until {
+                is_last_page |= channel_messages.iter().any(|message| message.id >= until);
+                channel_messages.retain(|message| message.id <= until);
+            }
 
-        println!("{guild_id} done");
+            channel_messages.reverse();
+            messages.append(&mut channel_messages);
 
-        Ok(())
+            self.check_message_count(
+                messages
+                    .iter()
+                    .filter(|message| {
+                        author_ids.is_empty() || author_ids.contains(&message.author.id)
+                    })
+                    .count(),
+            )?;
+            if messages.len() > SCAN_LIMIT {
+                return Err(CustomError::TooManyMessagesToScan(SCAN_LIMIT).into());
+            }
+            if is_last_page {
+                return Ok(());
+            }
+        }
     }
 }
@@ -0,0 +1,94 @@
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use sparkle_convenience::{error::IntoError, interaction::extract::InteractionExt, reply::Reply};
+use twilight_model::application::command::{Command, CommandType};
+use twilight_util::builder::command::CommandBuilder;
+
+use crate::{interaction::InteractionContext, job::Mode, CustomError};
+
+pub const START_NAME: &str = "move messages from here";
+pub const END_NAME: &str = "move messages until here";
+
+pub fn start_command() -> Command {
+    CommandBuilder::new(START_NAME, "", CommandType::Message)
+        .dm_permission(false)
+        .build()
+}
+
+pub fn end_command() -> Command {
+    CommandBuilder::new(END_NAME, "", CommandType::Message)
+        .dm_permission(false)
+        .build()
+}
+
+impl InteractionContext<'_> {
+    pub async fn handle_move_messages_from_here_command(self) -> Result<()> {
+        let (message, _) = self.handle_message_command().await?;
+        let user_id = self.interaction.user().ok()?.id;
+        let expires_at = self.prompt_deadline()?;
+
+        {
+            let mut range_starts = self
+                .ctx
+                .range_starts
+                .lock()
+                .map_err(|_| anyhow!("range starts mutex is poisoned"))?;
+            let now = Instant::now();
+            range_starts.retain(|_, (_, expires_at)| *expires_at > now);
+            range_starts.insert(user_id, (message, expires_at));
+        }
+
+        self.handle
+            .reply(Reply::new().ephemeral().content(format!(
+                "noted, now use **{END_NAME}** on the last message you want to move"
+            )))
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn handle_move_messages_until_here_command(self) -> Result<()> {
+        let guild_id = self.interaction.guild_id.ok()?;
+
+        let (end_message, can_move_others) = self.handle_message_command().await?;
+        let user_id = self.interaction.user().ok()?.id;
+
+        let start_message = self
+            .ctx
+            .range_starts
+            .lock()
+            .map_err(|_| anyhow!("range starts mutex is poisoned"))?
+            .remove(&user_id)
+            .and_then(|(message, expires_at)| (expires_at > Instant::now()).then_some(message))
+            .ok_or(CustomError::RangeStartMissing)?;
+        if start_message.channel_id != end_message.channel_id {
+            return Err(CustomError::RangeInDifferentChannels.into());
+        }
+
+        let (start_message, end_message) = if start_message.id <= end_message.id {
+            (start_message, end_message)
+        } else {
+            (end_message, start_message)
+        };
+
+        let Some((destination, author_ids)) =
+            Box::pin(self.wait_for_channel_select_interaction(&start_message, true, Mode::Move))
+                .await?
+        else {
+            return Ok(());
+        };
+
+        let end_message_id = end_message.id;
+        let mut messages = vec![start_message];
+        if end_message_id != messages[0].id {
+            self.append_messages_below(&mut messages, Some(end_message_id), &author_ids)
+                .await?;
+        }
+        let (messages, rejected) =
+            self.partition_movable(messages, &author_ids, can_move_others)?;
+
+        self.move_messages(&messages, &rejected, destination, guild_id, Mode::Move)
+            .await
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::{
+        extract::{InteractionDataExt, InteractionExt},
+        DeferBehavior, DeferVisibility,
+    },
+    reply::Reply,
+};
+use twilight_model::{
+    application::interaction::{Interaction, InteractionData},
+    channel::{
+        message::component::{TextInput, TextInputStyle},
+        ChannelType, Message,
+    },
+    gateway::event::Event,
+    guild::Permissions,
+    id::{marker::ChannelMarker, Id},
+};
+
+use crate::{
+    interaction::{destination::Destination, InteractionContext},
+    permission::DESTINATION_BOT_PERMISSIONS,
+    CustomError,
+};
+
+pub const MODAL_CUSTOM_ID: &str = "new_thread";
+const NAME_INPUT_CUSTOM_ID: &str = "name";
+
+/// The maximum number of characters in a thread name
+const THREAD_NAME_LENGTH_MAX: u16 = 100;
+
+/// The types of channels new threads can be created in
+pub const THREAD_PARENT_CHANNEL_TYPES: [ChannelType; 2] =
+    [ChannelType::GuildText, ChannelType::GuildAnnouncement];
+
+impl InteractionContext<'_> {
+    /// Ask the user for the name of the thread with a modal as the response to
+    /// the interaction, returning the new thread in the channel, or its parent
+    /// if it's a thread, as the destination
+    ///
+    /// Creates a new post instead if the channel is a forum
+    pub async fn new_thread_from_modal(
+        &self,
+        interaction: &Interaction,
+        channel_id: Id<ChannelMarker>,
+        first_message: &Message,
+    ) -> Result<Destination> {
+        let mut channel = self.ctx.bot.http.channel(channel_id).await?.model().await?;
+        if channel.kind.is_thread() {
+            channel = self
+                .ctx
+                .bot
+                .http
+                .channel(channel.parent_id.ok()?)
+                .await?
+                .model()
+                .await?;
+        }
+
+        if channel.kind == ChannelType::GuildForum {
+            return Box::pin(self.new_forum_post_from_modal(interaction, channel, first_message))
+                .await;
+        }
+        if !THREAD_PARENT_CHANNEL_TYPES.contains(&channel.kind) {
+            self.ctx
+                .bot
+                .interaction_handle(interaction)
+                .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
+                .await?;
+            return Err(CustomError::ThreadsUnsupported(channel.id).into());
+        }
+
+        let name = self
+            .wait_for_thread_name(interaction, first_message, "create a new thread")
+            .await?;
+
+        let guild_id = self.interaction.guild_id.ok()?;
+        self.ctx.check_destination_allowed(&channel, guild_id)?;
+        self.ctx
+            .check_permissions(
+                &channel,
+                guild_id,
+                self.interaction.member.as_ref().ok()?,
+                Permissions::VIEW_CHANNEL
+                    | Permissions::CREATE_PUBLIC_THREADS
+                    | Permissions::SEND_MESSAGES_IN_THREADS,
+                DESTINATION_BOT_PERMISSIONS | Permissions::CREATE_PUBLIC_THREADS,
+            )
+            .await?;
+
+        Ok(Destination::NewThread {
+            parent: channel,
+            name,
+        })
+    }
+
+    /// Respond to the interaction with a modal asking for the name of the
+    /// thread or post and wait for the user to submit it
+    ///
+    /// The name is filled with the first line of `first_message` by default
+    pub async fn wait_for_thread_name(
+        &self,
+        interaction: &Interaction,
+        first_message: &Message,
+        title: &str,
+    ) -> Result<String> {
+        self.ctx
+            .bot
+            .interaction_handle(interaction)
+            .modal(
+                MODAL_CUSTOM_ID.to_owned(),
+                title.to_owned(),
+                vec![TextInput {
+                    custom_id: NAME_INPUT_CUSTOM_ID.to_owned(),
+                    label: "the name".to_owned(),
+                    max_length: Some(THREAD_NAME_LENGTH_MAX),
+                    min_length: Some(1),
+                    placeholder: None,
+                    required: Some(true),
+                    style: TextInputStyle::Short,
+                    value: Some(default_thread_name(first_message)),
+                }],
+            )
+            .await?;
+
+        let user_id = self.interaction.user().ok()?.id;
+        let modal_event = tokio::time::timeout_at(
+            self.prompt_deadline()?.into(),
+            self.ctx.standby.wait_for_event(move |event: &Event| {
+                let Event::InteractionCreate(interaction) = event else {
+                    return false;
+                };
+
+                interaction.author_id() == Some(user_id)
+                    && matches!(
+                        &interaction.data,
+                        Some(InteractionData::ModalSubmit(data)) if data.custom_id == MODAL_CUSTOM_ID
+                    )
+            }),
+        )
+        .await
+        .map_err(|_| CustomError::PromptExpired)??;
+        let Event::InteractionCreate(modal_interaction) = modal_event else {
+            return Err(anyhow!("standby returned a non-interaction event"));
+        };
+
+        self.ctx
+            .bot
+            .interaction_handle(&modal_interaction)
+            .defer_with_behavior(DeferVisibility::Ephemeral, DeferBehavior::Update)
+            .await?;
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .update_last()
+                    .content("noted, doing some checks :face_with_monocle:"),
+            )
+            .await?;
+
+        modal_interaction
+            .0
+            .data
+            .clone()
+            .ok()?
+            .modal()
+            .ok()?
+            .components
+            .into_iter()
+            .flat_map(|row| row.components)
+            .find(|component| component.custom_id == NAME_INPUT_CUSTOM_ID)
+            .ok()?
+            .value
+            .ok()
+    }
+}
+
+/// The first line of the message, cut to fit in a thread name, or a generic
+/// name if the message has no content
+pub fn default_thread_name(message: &Message) -> String {
+    let first_line = message.content.lines().next().unwrap_or_default().trim();
+    if first_line.is_empty() {
+        return "moved messages".to_owned();
+    }
+
+    first_line
+        .chars()
+        .take(usize::from(THREAD_NAME_LENGTH_MAX))
+        .collect()
+}
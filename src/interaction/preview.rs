@@ -0,0 +1,213 @@
+use anyhow::Result;
+use twilight_model::{
+    channel::message::{Message, MessageType},
+    guild::PremiumTier,
+    id::{marker::UserMarker, Id},
+};
+use twilight_validate::message::MESSAGE_CONTENT_LENGTH_MAX;
+
+use crate::{
+    interaction::{destination::Destination, InteractionContext},
+    job::Mode,
+    message::upload_limit,
+    CustomError,
+};
+
+/// The most characters of a message's first line to show in the preview
+const FIRST_LINE_LENGTH_MAX: usize = 50;
+
+/// A message that won't be moved and why
+pub type RejectedMessage = (Message, Rejection);
+
+/// Why a message won't be moved
+#[derive(Clone, Copy, Debug)]
+pub enum Rejection {
+    NotFromAuthors,
+    NotOwnMessage,
+    SystemMessage,
+}
+
+impl Rejection {
+    const fn reason(self) -> &'static str {
+        match self {
+            Self::NotFromAuthors => "it's not sent by the users you selected",
+            Self::NotOwnMessage => "you need manage messages permissions to move others' messages",
+            Self::SystemMessage => "it's a system message",
+        }
+    }
+}
+
+impl InteractionContext<'_> {
+    /// Split the messages into the ones to move and the ones that won't be
+    /// moved, returning an error if none of them can be moved
+    ///
+    /// If `author_ids` isn't empty, messages not sent by these users are
+    /// rejected, `can_move_others` is whether the user can move other people's
+    /// messages in the channel of the messages
+    pub fn partition_movable(
+        &self,
+        messages: Vec<Message>,
+        author_ids: &[Id<UserMarker>],
+        can_move_others: bool,
+    ) -> Result<(Vec<Message>, Vec<RejectedMessage>)> {
+        let mut movable = vec![];
+        let mut rejected = vec![];
+
+        for message in messages {
+            if !matches!(
+                message.kind,
+                MessageType::Regular
+                    | MessageType::Reply
+                    | MessageType::ChatInputCommand
+                    | MessageType::ContextMenuCommand
+            ) {
+                rejected.push((message, Rejection::SystemMessage));
+            } else if !author_ids.is_empty() && !author_ids.contains(&message.author.id) {
+                rejected.push((message, Rejection::NotFromAuthors));
+            } else if self.check_can_move(&message, can_move_others).is_err() {
+                rejected.push((message, Rejection::NotOwnMessage));
+            } else {
+                movable.push(message);
+            }
+        }
+
+        if movable.is_empty() {
+            return Err(if rejected
+                .iter()
+                .all(|(_, rejection)| matches!(rejection, Rejection::NotFromAuthors))
+            {
+                CustomError::NoMessagesFromAuthors
+            } else {
+                CustomError::NoMovableMessages
+            }
+            .into());
+        }
+
+        Ok((movable, rejected))
+    }
+}
+
+/// The content listing the messages that will be moved or copied and the ones
+/// that won't, asking to confirm
+pub fn preview(
+    messages: &[Message],
+    rejected: &[RejectedMessage],
+    destination: &Destination,
+    mode: Mode,
+) -> String {
+    let header = format!(
+        "i'm going to {} {} messages to {}:\n",
+        mode.verb(),
+        messages.len(),
+        destination.mention()
+    );
+    let rejected_header = format!(
+        "\nthese {} messages won't be {}:\n",
+        rejected.len(),
+        mode.past_participle()
+    );
+    let footer = if mode == Mode::Move && messages.iter().any(has_large_attachments) {
+        "\nsome attachments might be too big for me to upload again, i'll link to them instead \
+         but the links will stop working once the original messages are deleted, should i go on?"
+    } else {
+        "\nshould i go on?"
+    };
+
+    let mut budget = MESSAGE_CONTENT_LENGTH_MAX - header.len() - footer.len();
+    let rejected_list = if rejected.is_empty() {
+        String::new()
+    } else {
+        budget -= rejected_header.len();
+        let rejected_list = list(
+            rejected.iter().map(|(message, rejection)| {
+                format!("{}: {}", message_summary(message), rejection.reason())
+            }),
+            budget / 3,
+        );
+        budget -= rejected_list.chars().count();
+        rejected_list
+    };
+
+    let mut content = header;
+    content.push_str(&list(messages.iter().map(message_summary), budget));
+    if !rejected.is_empty() {
+        content.push_str(&rejected_header);
+        content.push_str(&rejected_list);
+    }
+    content.push_str(footer);
+
+    content
+}
+
+/// Join the lines as a list, with the count of the lines left out to keep it
+/// under the given length
+fn list(lines: impl ExactSizeIterator<Item = String>, length_max: usize) -> String {
+    let line_count = lines.len();
+    let more_length_max = format!("and {line_count} more\n").len();
+
+    let mut list = vec![];
+    let mut list_length = 0;
+    for (idx, line) in lines.enumerate() {
+        let line = format!("- {line}\n");
+        let is_last = idx + 1 == line_count;
+        let reserved = if is_last { 0 } else { more_length_max };
+
+        if list_length + line.chars().count() + reserved > length_max {
+            list.push(format!("and {} more\n", line_count - idx));
+            break;
+        }
+        list_length += line.chars().count();
+        list.push(line);
+    }
+
+    list.concat()
+}
+
+/// Whether the message's attachments might be too big to upload again in a
+/// guild without boosts, in which case they're linked instead
+fn has_large_attachments(message: &Message) -> bool {
+    message
+        .attachments
+        .iter()
+        .map(|attachment| attachment.size)
+        .sum::<u64>()
+        > upload_limit(PremiumTier::None)
+}
+
+/// The author, timestamp, first line and attachment and embed counts of the
+/// message
+fn message_summary(message: &Message) -> String {
+    let first_line = message.content.lines().next().unwrap_or_default();
+    let first_line = if first_line.is_empty() {
+        "*no content*".to_owned()
+    } else if first_line.chars().count() > FIRST_LINE_LENGTH_MAX {
+        format!(
+            "{}…",
+            first_line
+                .chars()
+                .take(FIRST_LINE_LENGTH_MAX)
+                .collect::<String>()
+        )
+    } else {
+        first_line.to_owned()
+    };
+
+    let summary = format!(
+        "**{}** <t:{}:f>: {first_line}",
+        message.author.name,
+        message.timestamp.as_secs()
+    );
+
+    let mut counts = vec![];
+    if !message.attachments.is_empty() {
+        counts.push(format!("{} attachments", message.attachments.len()));
+    }
+    if !message.embeds.is_empty() {
+        counts.push(format!("{} embeds", message.embeds.len()));
+    }
+    if counts.is_empty() {
+        summary
+    } else {
+        format!("{summary} ({})", counts.join(", "))
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use sparkle_convenience::error::IntoError;
+use twilight_model::{
+    channel::{message::AllowedMentions, Message},
+    guild::Permissions,
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_validate::message::MESSAGE_CONTENT_LENGTH_MAX;
+
+use crate::{config::SourceNotice, interaction::InteractionContext};
+
+impl InteractionContext<'_> {
+    /// Post a notice in the source channel saying where the messages were
+    /// moved to, if the guild enabled it and the bot can send messages there
+    pub async fn post_source_notice(
+        &self,
+        originals: &[Message],
+        destination_channel_id: Id<ChannelMarker>,
+        first_copy: &Message,
+    ) -> Result<Option<Message>> {
+        let guild_id = self.interaction.guild_id.ok()?;
+        if self.ctx.config.guild(guild_id)?.source_notice == SourceNotice::Disabled {
+            return Ok(None);
+        }
+
+        let source_channel = self
+            .ctx
+            .bot
+            .http
+            .channel(originals[0].channel_id)
+            .await?
+            .model()
+            .await?;
+        let send_permission = if source_channel.kind.is_thread() {
+            Permissions::SEND_MESSAGES_IN_THREADS
+        } else {
+            Permissions::SEND_MESSAGES
+        };
+        // the interaction is always used in the source channel
+        if !self
+            .interaction
+            .app_permissions
+            .ok()?
+            .contains(send_permission)
+        {
+            return Ok(None);
+        }
+
+        let mut author_ids: Vec<Id<UserMarker>> = vec![];
+        for message in originals {
+            if !author_ids.contains(&message.author.id) {
+                author_ids.push(message.author.id);
+            }
+        }
+        let mut authors = author_ids
+            .iter()
+            .map(|author_id| format!("<@{author_id}>"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        // the rest of the content is always shorter than 200 characters
+        if authors.chars().count() + 200 > MESSAGE_CONTENT_LENGTH_MAX {
+            authors = format!("{} users", author_ids.len());
+        }
+
+        let content = if originals.len() == 1 {
+            format!(
+                "a message by {authors} was moved to <#{destination_channel_id}>: \
+                 https://discord.com/channels/{guild_id}/{}/{}",
+                first_copy.channel_id, first_copy.id
+            )
+        } else {
+            format!(
+                "{} messages by {authors} were moved to <#{destination_channel_id}>: \
+                 https://discord.com/channels/{guild_id}/{}/{}",
+                originals.len(),
+                first_copy.channel_id,
+                first_copy.id
+            )
+        };
+
+        Ok(Some(
+            self.ctx
+                .bot
+                .http
+                .create_message(originals[0].channel_id)
+                .allowed_mentions(Some(&AllowedMentions::default()))
+                .content(&content)?
+                .await?
+                .model()
+                .await?,
+        ))
+    }
+}
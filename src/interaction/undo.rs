@@ -0,0 +1,290 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use sparkle_convenience::{
+    error::IntoError,
+    interaction::extract::{InteractionDataExt, InteractionExt},
+    reply::Reply,
+};
+use twilight_model::{
+    channel::{
+        message::{
+            component::{ActionRow, Button, ButtonStyle},
+            Component,
+        },
+        Channel, Message,
+    },
+    id::{
+        marker::{ChannelMarker, InteractionMarker, MessageMarker},
+        Id,
+    },
+};
+
+use crate::{
+    config::SourceNotice,
+    interaction::{config::SOURCE_NOTICE_DELETE_AFTER_LIMIT, InteractionContext},
+    job::Mode,
+    CustomError,
+};
+
+/// The start of the undo button's custom ID, followed by the ID of the
+/// interaction that started the move
+pub const CUSTOM_ID: &str = "undo_move";
+
+/// The last move of a user, kept to undo it
+pub struct LastMove {
+    /// The ID of the interaction that started the move, to only undo it from
+    /// its own button
+    id: Id<InteractionMarker>,
+    source_channel_id: Id<ChannelMarker>,
+    destination_channel_id: Id<ChannelMarker>,
+    mode: Mode,
+    /// The notice posted in the source channel, deleted when the move is undone
+    source_notice: Option<Message>,
+    /// The copies of the moved messages in the order they were moved, with
+    /// the author of the original message to send them back as them
+    copies: Vec<Message>,
+    expires_at: Instant,
+}
+
+impl InteractionContext<'_> {
+    /// Post the source notice if the messages were moved, remember the move to
+    /// be able to undo it and reply that it's done, with an undo button if
+    /// undoing is enabled
+    ///
+    /// Undoing a copy only deletes the copies
+    pub async fn finish_move(
+        &self,
+        originals: &[Message],
+        destination_channel_id: Id<ChannelMarker>,
+        mode: Mode,
+        copies: Vec<(Id<MessageMarker>, Vec<Message>)>,
+    ) -> Result<()> {
+        let guild_id = self.interaction.guild_id.ok()?;
+        let user_id = self.interaction.user().ok()?.id;
+        let config = self.ctx.config.guild(guild_id)?;
+
+        let source_notice = match (mode, copies.first().and_then(|(_, copies)| copies.first())) {
+            (Mode::Move, Some(first_copy)) => {
+                match self
+                    .post_source_notice(originals, destination_channel_id, first_copy)
+                    .await
+                {
+                    Ok(source_notice) => source_notice,
+                    Err(err) => {
+                        self.ctx.bot.log(&err).await;
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let source_notice_id = source_notice.as_ref().map(|notice| notice.id);
+
+        let mut reply = Reply::new()
+            .ephemeral()
+            .update_last()
+            .content("done :incoming_envelope:");
+
+        if config.undo_window_minutes > 0 {
+            let now = Instant::now();
+            let mut last_moves = self
+                .ctx
+                .last_moves
+                .lock()
+                .map_err(|_| anyhow!("last moves mutex is poisoned"))?;
+            last_moves.retain(|_, last_move| last_move.expires_at > now);
+            last_moves.insert(
+                user_id,
+                LastMove {
+                    id: self.interaction.id,
+                    source_channel_id: originals[0].channel_id,
+                    destination_channel_id,
+                    mode,
+                    source_notice,
+                    copies: copies_as_authors(originals, copies),
+                    expires_at: now + Duration::from_secs(config.undo_window_minutes * 60),
+                },
+            );
+            drop(last_moves);
+
+            reply = reply.component(Component::ActionRow(ActionRow {
+                components: vec![Component::Button(Button {
+                    custom_id: Some(format!("{CUSTOM_ID}:{}", self.interaction.id)),
+                    disabled: false,
+                    emoji: None,
+                    label: Some("undo".to_owned()),
+                    style: ButtonStyle::Danger,
+                    url: None,
+                })],
+            }));
+        }
+
+        self.handle.reply(reply).await?;
+
+        if let (SourceNotice::AutoDelete, Some(source_notice_id)) =
+            (config.source_notice, source_notice_id)
+        {
+            // older configs might have a longer delay than the limit
+            let delete_after_minutes = config
+                .source_notice_delete_after_minutes
+                .min(SOURCE_NOTICE_DELETE_AFTER_LIMIT.unsigned_abs());
+            tokio::time::sleep(Duration::from_secs(delete_after_minutes * 60)).await;
+            // the notice might already be deleted by undoing the move
+            let _ = self
+                .ctx
+                .bot
+                .http
+                .delete_message(originals[0].channel_id, source_notice_id)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    pub async fn handle_undo_button(self) -> Result<()> {
+        let user_id = self.interaction.user().ok()?.id;
+        let move_id = self
+            .interaction
+            .data
+            .clone()
+            .ok()?
+            .component()
+            .ok()?
+            .custom_id
+            .split_once(':')
+            .and_then(|(_, move_id)| move_id.parse::<Id<InteractionMarker>>().ok())
+            .ok_or(CustomError::NothingToUndo)?;
+
+        let last_move = {
+            let mut last_moves = self
+                .ctx
+                .last_moves
+                .lock()
+                .map_err(|_| anyhow!("last moves mutex is poisoned"))?;
+            if !last_moves.get(&user_id).is_some_and(|last_move| {
+                last_move.id == move_id && last_move.expires_at > Instant::now()
+            }) {
+                return Err(CustomError::NothingToUndo.into());
+            }
+            last_moves.remove(&user_id).ok()?
+        };
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .update_last()
+                    .content(match last_move.mode {
+                        Mode::Move => "moving them back :leftwards_arrow_with_hook:",
+                        Mode::Copy => "deleting the copies :wastebasket:",
+                    }),
+            )
+            .await?;
+
+        let source_channel = self
+            .ctx
+            .bot
+            .http
+            .channel(last_move.source_channel_id)
+            .await?
+            .model()
+            .await?;
+
+        if last_move.mode == Mode::Move {
+            let mut moved_back = vec![];
+            if let Err(err) = self
+                .move_back(&last_move.copies, &source_channel, &mut moved_back)
+                .await
+            {
+                self.ctx.bot.log(&err).await;
+                if let Err(err) = self.ctx.delete_copies(&moved_back).await {
+                    self.ctx.bot.log(&err).await;
+                    return Err(CustomError::RollbackFailed(last_move.source_channel_id).into());
+                }
+
+                // the move is left as it was, so it can be undone again
+                self.ctx
+                    .last_moves
+                    .lock()
+                    .map_err(|_| anyhow!("last moves mutex is poisoned"))?
+                    .insert(user_id, last_move);
+                return Err(CustomError::UndoFailed.into());
+            }
+        }
+
+        if let Err(err) = self.ctx.delete_copies(&last_move.copies).await {
+            self.ctx.bot.log(&err).await;
+            return Err(CustomError::DeletingCopiesFailed(last_move.destination_channel_id).into());
+        }
+
+        if let Some(source_notice) = last_move.source_notice {
+            // the notice might already be deleted if it's auto-deleted
+            let _ = self
+                .ctx
+                .bot
+                .http
+                .delete_message(source_notice.channel_id, source_notice.id)
+                .await;
+        }
+
+        self.handle
+            .reply(
+                Reply::new()
+                    .ephemeral()
+                    .update_last()
+                    .content(match last_move.mode {
+                        Mode::Move => format!(
+                            "moved them back from <#{}> to <#{}> :incoming_envelope:",
+                            last_move.destination_channel_id, last_move.source_channel_id
+                        ),
+                        Mode::Copy => format!(
+                            "deleted the copies in <#{}> :wastebasket:",
+                            last_move.destination_channel_id
+                        ),
+                    }),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Send the copies back to the source channel as their original authors,
+    /// pushing the messages sent back to `moved_back`
+    async fn move_back(
+        &self,
+        copies: &[Message],
+        source_channel: &Channel,
+        moved_back: &mut Vec<Message>,
+    ) -> Result<()> {
+        for copy in copies {
+            self.ctx
+                .execute_webhook_as_member(copy, source_channel, None, moved_back)
+                .await?;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// The copies of the messages with their author and member set to the ones of
+/// their original message, since the author of the copies is the webhook
+fn copies_as_authors(
+    originals: &[Message],
+    copies: Vec<(Id<MessageMarker>, Vec<Message>)>,
+) -> Vec<Message> {
+    copies
+        .into_iter()
+        .flat_map(|(original_id, copies)| {
+            let original = originals.iter().find(|original| original.id == original_id);
+            copies.into_iter().map(move |mut copy| {
+                if let Some(original) = original {
+                    copy.author.clone_from(&original.author);
+                    copy.member.clone_from(&original.member);
+                }
+                copy
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,237 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use twilight_http::error::ErrorType;
+use twilight_model::{
+    channel::Message,
+    id::{
+        marker::{ChannelMarker, GuildMarker, InteractionMarker, MessageMarker},
+        Id,
+    },
+};
+
+use crate::{file, message::replied_copy, Context};
+
+/// Whether the original messages are deleted after they're sent to the
+/// destination channel
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Move,
+    Copy,
+}
+
+impl Mode {
+    pub const fn verb(self) -> &'static str {
+        match self {
+            Self::Move => "move",
+            Self::Copy => "copy",
+        }
+    }
+
+    pub const fn past_participle(self) -> &'static str {
+        match self {
+            Self::Move => "moved",
+            Self::Copy => "copied",
+        }
+    }
+}
+
+/// A move that's in progress, saved so that it can be resumed if the bot
+/// restarts in the middle of it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Job {
+    pub guild_id: Id<GuildMarker>,
+    pub source_channel_id: Id<ChannelMarker>,
+    pub destination_channel_id: Id<ChannelMarker>,
+    #[serde(default)]
+    pub mode: Mode,
+    /// The IDs of the messages to move, oldest first
+    pub message_ids: Vec<Id<MessageMarker>>,
+    /// How many of the messages are already sent to the destination channel
+    pub copied_count: usize,
+    /// The token of the interaction that started the move, to report the
+    /// outcome of resuming it
+    pub interaction_token: String,
+}
+
+/// The jobs in progress, saved to a JSON file whenever they're updated
+///
+/// The file contains interaction tokens, so it should be kept private
+pub struct Jobs {
+    path: String,
+    jobs: Mutex<HashMap<Id<InteractionMarker>, Job>>,
+}
+
+impl Jobs {
+    pub fn load(path: String) -> Result<Self> {
+        Ok(Self {
+            jobs: Mutex::new(file::load(&path)?),
+            path,
+        })
+    }
+
+    pub fn all(&self) -> Result<Vec<(Id<InteractionMarker>, Job)>> {
+        Ok(self
+            .jobs
+            .lock()
+            .map_err(|_| anyhow!("jobs mutex is poisoned"))?
+            .iter()
+            .map(|(id, job)| (*id, job.clone()))
+            .collect())
+    }
+
+    pub fn insert(&self, id: Id<InteractionMarker>, job: Job) -> Result<()> {
+        self.modify(|jobs| {
+            jobs.insert(id, job);
+        })
+    }
+
+    pub fn increment_copied_count(&self, id: Id<InteractionMarker>) -> Result<()> {
+        self.modify(|jobs| {
+            if let Some(job) = jobs.get_mut(&id) {
+                job.copied_count += 1;
+            }
+        })
+    }
+
+    pub fn remove(&self, id: Id<InteractionMarker>) -> Result<()> {
+        self.modify(|jobs| {
+            jobs.remove(&id);
+        })
+    }
+
+    fn modify(&self, modify: impl FnOnce(&mut HashMap<Id<InteractionMarker>, Job>)) -> Result<()> {
+        let mut jobs = self
+            .jobs
+            .lock()
+            .map_err(|_| anyhow!("jobs mutex is poisoned"))?;
+
+        modify(&mut jobs);
+        file::save(&self.path, &*jobs)?;
+        drop(jobs);
+
+        Ok(())
+    }
+}
+
+impl Context {
+    /// Finish the jobs that were in progress when the bot stopped, reporting
+    /// their outcome to the user that started them or the logging channel
+    pub async fn resume_jobs(&self) -> Result<()> {
+        for (id, mut job) in self.jobs.all()? {
+            println!(
+                "resuming the move in {}: {}/{}",
+                job.guild_id,
+                job.copied_count,
+                job.message_ids.len()
+            );
+
+            let content = match Box::pin(self.resume_job(id, &mut job)).await {
+                Ok(()) => format!(
+                    "i restarted while working on the messages, but i picked up where i left off \
+                     and {} them :incoming_envelope:",
+                    job.mode.past_participle()
+                ),
+                Err(err) => {
+                    self.bot.log(&err).await;
+                    format!(
+                        "i restarted while working on the messages and couldn't finish, \
+                         {} of the {} messages are sent to <#{}> and the original messages \
+                         might not be deleted",
+                        job.copied_count,
+                        job.message_ids.len(),
+                        job.destination_channel_id
+                    )
+                }
+            };
+            self.jobs.remove(id)?;
+
+            if self
+                .bot
+                .interaction_client()
+                .update_response(&job.interaction_token)
+                .content(Some(&content))?
+                .await
+                .is_err()
+            {
+                self.bot
+                    .log(format!(
+                        "resumed the move from {} to {} in {}: {content}",
+                        job.source_channel_id, job.destination_channel_id, job.guild_id
+                    ))
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn resume_job(&self, id: Id<InteractionMarker>, job: &mut Job) -> Result<()> {
+        let channel = self
+            .bot
+            .http
+            .channel(job.destination_channel_id)
+            .await?
+            .model()
+            .await?;
+
+        let mut copies = vec![];
+        while let Some(message_id) = job.message_ids.get(job.copied_count).copied() {
+            if let Some(message) = self.message(job.source_channel_id, message_id).await? {
+                let mut message_copies = vec![];
+                let execute_res = self
+                    .execute_webhook_as_member(
+                        &message,
+                        &channel,
+                        replied_copy(&message, &copies),
+                        &mut message_copies,
+                    )
+                    .await;
+                copies.push((message_id, message_copies));
+                execute_res?;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            self.jobs.increment_copied_count(id)?;
+            job.copied_count += 1;
+        }
+
+        if job.mode == Mode::Copy {
+            return Ok(());
+        }
+
+        let mut messages = vec![];
+        for message_id in &job.message_ids {
+            if let Some(message) = self.message(job.source_channel_id, *message_id).await? {
+                messages.push(message);
+            }
+        }
+        if !messages.is_empty() {
+            self.delete_messages(&messages, job.guild_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// The message with the given ID or `None` if it's deleted
+    async fn message(
+        &self,
+        channel_id: Id<ChannelMarker>,
+        message_id: Id<MessageMarker>,
+    ) -> Result<Option<Message>> {
+        match self.bot.http.message(channel_id, message_id).await {
+            Ok(response) => Ok(Some(response.model().await?)),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorType::Response { status, .. } if status.get() == 404
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
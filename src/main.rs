@@ -1,6 +1,11 @@
 #![warn(clippy::nursery, clippy::pedantic)]
 
-use std::{env, sync::Arc};
+use std::{
+    collections::HashMap,
+    env,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use anyhow::Result;
 use futures::StreamExt;
@@ -15,19 +20,28 @@ use twilight_gateway::{
     error::ReceiveMessageErrorType, stream::ShardEventStream, EventTypeFlags, Intents,
 };
 use twilight_model::{
+    channel::Message,
     gateway::event::Event,
     guild::Permissions,
     id::{
-        marker::{ChannelMarker, GuildMarker},
+        marker::{ChannelMarker, GuildMarker, UserMarker},
         Id,
     },
 };
 use twilight_standby::Standby;
 
-use crate::interaction::set_commands;
+use crate::{
+    config::Config,
+    interaction::{set_commands, LastMove},
+    job::Jobs,
+};
 
+mod config;
+mod file;
 mod interaction;
+mod job;
 mod message;
+mod permission;
 
 const TEST_GUILD_ID: Id<GuildMarker> = Id::new(903_367_565_349_384_202);
 const LOGGING_CHANNEL_ID: Id<ChannelMarker> = Id::new(1_002_953_459_890_397_287);
@@ -45,34 +59,107 @@ pub enum Error {
 
 #[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
 pub enum CustomError {
-    #[error("you need **Manage Messages** permissions to move messages that are not your own")]
-    ManageMessagesPermissionsMissing,
     #[error(
-        "you need **Send Messages** permissions in the channel you want to move the messages to"
+        "you need **Manage Messages** permissions or a role admins let move other people's \
+         messages to move messages that are not your own"
     )]
-    SendMessagesPermissionMissing,
+    ManageMessagesPermissionsMissing,
+    #[error("you can't view <#{0}>")]
+    ChannelHiddenFromMember(Id<ChannelMarker>),
+    #[error("you need these permissions in <#{0}> first:\n{1}")]
+    MemberPermissionsMissing(Id<ChannelMarker>, String),
+    #[error("i can't view <#{0}>, please beg the mods to let me see it first")]
+    ChannelHiddenFromBot(Id<ChannelMarker>),
+    #[error("i'm not in <#{0}>, please add me to it first by mentioning me in it")]
+    BotNotInThread(Id<ChannelMarker>),
+    #[error("please beg the mods to give me these permissions in <#{0}> first:\n{1}")]
+    BotPermissionsMissing(Id<ChannelMarker>, String),
     #[error("one of the messages is too long, you're probably using your super nitro powers")]
     MessageTooLong,
     #[error(
-        "one of the messages has an image/file, this will be handled better in the future, sorry \
-         for the inconvenience and stay tuned!"
+        "i can work with up to {0} messages in this server, admins can change this with `/config \
+         max-messages`"
+    )]
+    TooManyMessages(usize),
+    #[error(
+        "i can only move up to {0} messages before i can't tell you how it went anymore, admins \
+         can lower how long i wait for you with `/config prompt-timeout` to let me move more"
     )]
-    MessageAttachment,
+    TooManyMessagesInTime(usize),
+    #[error("i can look through up to {0} messages at once, try moving fewer messages")]
+    TooManyMessagesToScan(usize),
     #[error(
-        "i can work with up to 50 messages, if you need a higher limit, please join the support \
-         server and tell lara"
+        "use **move messages from here** on the first message you want to move first, then use \
+         this on the last one"
     )]
-    TooManyMessages,
+    RangeStartMissing,
+    #[error("the first and last messages you want to move have to be in the same channel")]
+    RangeInDifferentChannels,
+    #[error("none of the messages are sent by the users you selected")]
+    NoMessagesFromAuthors,
+    #[error("i couldn't find any messages to move")]
+    NoMessagesFound,
+    #[error("i can't move any of these messages")]
+    NoMovableMessages,
     #[error(
-        "i can't work with messages older than 2 weeks, if you need me to, please join the \
-         support server and tell lara"
+        "i couldn't understand that time, give me a unix timestamp like `1676917800` or a date \
+         like `2023-02-20T18:30:00Z`"
     )]
-    MessageTooOld,
+    InvalidTimestamp,
+    #[error("i can't undo that anymore, you can only undo your last move for a while after it")]
+    NothingToUndo,
+    #[error("you took too long to respond, use the command again if you still want to move them")]
+    PromptExpired,
+    #[error(
+        "something went wrong while moving the messages, so i deleted the messages i already sent \
+         and left the original messages as they were, i let lara know about it"
+    )]
+    MoveRolledBack,
+    #[error(
+        "something went wrong while moving the messages and i couldn't delete the messages i \
+         already sent to <#{0}>, please delete them yourself, the original messages are left as \
+         they were, i let lara know about it"
+    )]
+    RollbackFailed(Id<ChannelMarker>),
+    #[error(
+        "i sent all the messages but something went wrong while deleting the original ones, \
+         please delete what's left of them in <#{0}> yourself, i let lara know about it"
+    )]
+    DeletingOriginalsFailed(Id<ChannelMarker>),
+    #[error(
+        "something went wrong while moving them back, so i left everything as it was, you can try \
+         undoing again"
+    )]
+    UndoFailed,
+    #[error(
+        "something went wrong while deleting the copies, please delete what's left of them in \
+         <#{0}> yourself, i let lara know about it"
+    )]
+    DeletingCopiesFailed(Id<ChannelMarker>),
+    #[error("that forum requires tags on its posts, please use the message commands to pick them")]
+    ForumTagsRequired,
+    #[error("that forum requires tags on its posts but only moderators can apply them")]
+    ForumTagsModerated,
+    #[error("i can't create threads in <#{0}>, please pick another channel")]
+    ThreadsUnsupported(Id<ChannelMarker>),
+    #[error(
+        "admins only let some roles move messages in this server and you don't have any of them"
+    )]
+    RoleNotAllowed,
+    #[error("admins don't let messages be moved to <#{0}>")]
+    DestinationNotAllowed(Id<ChannelMarker>),
 }
 
 struct Context {
     bot: Bot,
     standby: Standby,
+    reqwest: reqwest::Client,
+    config: Config,
+    jobs: Jobs,
+    /// The messages users marked as the start of messages to move, with when
+    /// they expire
+    range_starts: Mutex<HashMap<Id<UserMarker>, (Message, Instant)>>,
+    last_moves: Mutex<HashMap<Id<UserMarker>, LastMove>>,
 }
 
 impl Context {
@@ -80,7 +167,7 @@ impl Context {
         self.standby.process(&event);
 
         if let Event::InteractionCreate(interaction) = event {
-            self.handle_interaction(interaction.0).await;
+            Box::pin(self.handle_interaction(interaction.0)).await;
         }
     }
 }
@@ -104,6 +191,18 @@ async fn main() -> Result<()> {
     let ctx = Arc::new(Context {
         bot,
         standby: Standby::new(),
+        reqwest: reqwest::Client::new(),
+        config: Config::load("config.json".to_owned())?,
+        jobs: Jobs::load("jobs.json".to_owned())?,
+        range_starts: Mutex::new(HashMap::new()),
+        last_moves: Mutex::new(HashMap::new()),
+    });
+
+    let ctx_ref = Arc::clone(&ctx);
+    tokio::spawn(async move {
+        if let Err(err) = Box::pin(ctx_ref.resume_jobs()).await {
+            ctx_ref.bot.log(&err).await;
+        }
     });
 
     let mut events = ShardEventStream::new(shards.iter_mut());
@@ -112,7 +211,7 @@ async fn main() -> Result<()> {
         match event_res {
             Ok(event) => {
                 tokio::spawn(async move {
-                    ctx_ref.handle_event(event).await;
+                    Box::pin(ctx_ref.handle_event(event)).await;
                 });
             }
             Err(err)
@@ -1,22 +1,265 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use anyhow::Result;
 use sparkle_convenience::error::IntoError;
-use twilight_model::channel::{Channel, Message};
+use twilight_model::{
+    channel::{
+        message::{
+            embed::{EmbedImage, EmbedThumbnail},
+            sticker::StickerFormatType,
+            Embed, MessageFlags,
+        },
+        Channel, Message, Webhook,
+    },
+    guild::PremiumTier,
+    http::attachment::Attachment,
+    id::{
+        marker::{ChannelMarker, GuildMarker},
+        Id,
+    },
+};
+use twilight_util::builder::embed::EmbedBuilder;
+use twilight_validate::{
+    embed::{self, DESCRIPTION_LENGTH, EMBED_TOTAL_LENGTH},
+    message::{EMBED_COUNT_LIMIT, MESSAGE_CONTENT_LENGTH_MAX},
+};
+
+use crate::{config::LongMessageStrategy, Context, CustomError};
+
+mod reaction;
+mod reply;
+mod split;
+
+pub use reply::replied_copy;
 
-use crate::{Context, CustomError};
+/// The maximum number of files in a message
+const ATTACHMENT_COUNT_LIMIT: usize = 10;
+
+/// The start of the line showing when the original message was sent
+const TIMESTAMP_FOOTER_PREFIX: &str = "-# sent <t:";
+
+/// The maximum number of messages that can be deleted in one request
+const BULK_DELETE_LIMIT: usize = 100;
+
+/// The age in seconds after which messages can't be bulk deleted, two weeks
+/// minus an hour to leave time for deleting them
+const BULK_DELETE_AGE_LIMIT_SECS: i64 = 14 * 24 * 60 * 60 - 60 * 60;
 
 impl Context {
+    /// Send a copy of the message to the channel with the author's name and
+    /// avatar, pushing each copy to `copies` as it's sent so that the copies
+    /// sent before an error can still be deleted
+    ///
+    /// If the message is a reply, the copy quotes the replied message, linking
+    /// to `replied_copy` if the replied message was also moved, the reactions
+    /// on the message are added to the last copy
     pub async fn execute_webhook_as_member(
         &self,
         message: &Message,
         channel: &Channel,
+        replied_copy: Option<&Message>,
+        copies: &mut Vec<Message>,
     ) -> Result<()> {
         let mut channel_id = channel.id;
         let mut thread_id = None;
         if channel.kind.is_thread() {
             thread_id = Some(channel_id);
             channel_id = channel.parent_id.ok()?;
+        }
+
+        let webhook = self.webhook(channel_id).await?;
+        let webhook_token = webhook.token.ok()?;
+
+        let guild_id = channel.guild_id.ok()?;
+        let config = self.config.guild(guild_id)?;
+        let allowed_mentions = config.mention_policy.allowed_mentions();
+
+        let (mut attachments, attachment_links) =
+            self.reupload_attachments(message, guild_id).await?;
+
+        let mut content = message.content.clone();
+        if let Some(header) = reply::reply_header(message, replied_copy) {
+            content = if content.is_empty() {
+                header
+            } else {
+                format!("{header}\n{content}")
+            };
+        }
+        for link in attachment_links {
+            push_line(&mut content, &link);
+        }
+        if config.reaction_summary {
+            if let Some(summary) = reaction::reaction_summary(message) {
+                push_line(&mut content, &summary);
+            }
+        }
+        if config.original_timestamps {
+            if let Some(footer) = timestamp_footer(message) {
+                push_line(&mut content, &footer);
+            }
+        }
+
+        let mut content_embed = None;
+        let contents = if content.chars().count() <= MESSAGE_CONTENT_LENGTH_MAX {
+            vec![content]
+        } else {
+            match config.long_message_strategy {
+                LongMessageStrategy::Embed if content.chars().count() <= DESCRIPTION_LENGTH => {
+                    content_embed = Some(EmbedBuilder::new().description(content).build());
+                    vec![String::new()]
+                }
+                // messages too long for an embed are sent as a file instead
+                LongMessageStrategy::Embed | LongMessageStrategy::File
+                    if attachments.len() < ATTACHMENT_COUNT_LIMIT =>
+                {
+                    attachments.push(Attachment::from_bytes(
+                        "message.txt".to_owned(),
+                        content.into_bytes(),
+                        // after the IDs of the reuploaded attachments and stickers
+                        (message.attachments.len() + message.sticker_items.len()).try_into()?,
+                    ));
+                    vec![String::new()]
+                }
+                _ => split::split(&content),
+            }
         };
 
+        let embeds = embeds(message, content_embed);
+
+        let username = message
+            .member
+            .as_ref()
+            .and_then(|member| member.nick.as_ref())
+            .unwrap_or(&message.author.name);
+
+        let avatar_url = avatar_url(message);
+
+        for (idx, content) in contents.iter().enumerate() {
+            let mut execute_webhook = self
+                .bot
+                .http
+                .execute_webhook(webhook.id, &webhook_token)
+                .allowed_mentions(Some(&allowed_mentions))
+                .username(username)?;
+
+            if !content.is_empty() {
+                execute_webhook = execute_webhook
+                    .content(content)
+                    .map_err(|_| CustomError::MessageTooLong)?;
+            }
+
+            if idx == contents.len() - 1 {
+                execute_webhook = execute_webhook.attachments(&attachments)?.embeds(&embeds)?;
+
+                if message
+                    .flags
+                    .is_some_and(|flags| flags.contains(MessageFlags::SUPPRESS_EMBEDS))
+                {
+                    execute_webhook = execute_webhook.flags(MessageFlags::SUPPRESS_EMBEDS);
+                }
+            }
+
+            if let Some(thread_id) = thread_id {
+                execute_webhook = execute_webhook.thread_id(thread_id);
+            }
+
+            if let Some(avatar_url) = &avatar_url {
+                execute_webhook = execute_webhook.avatar_url(avatar_url);
+            }
+
+            copies.push(execute_webhook.wait().await?.model().await?);
+        }
+
+        if let Some(last_copy) = copies.last() {
+            // the copies are already sent, so they're kept even if the reactions fail
+            if let Err(err) = self.add_reactions(message, last_copy).await {
+                self.bot.log(&err).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete the messages, which have to be in the same channel
+    ///
+    /// Messages recent enough are bulk deleted, the rest are deleted one by one
+    pub async fn delete_messages(
+        &self,
+        messages: &[Message],
+        guild_id: Id<GuildMarker>,
+    ) -> Result<()> {
+        let now = i64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+        let (mut single_messages, recent_messages): (Vec<_>, Vec<_>) = messages
+            .iter()
+            .partition(|message| now - message.timestamp.as_secs() > BULK_DELETE_AGE_LIMIT_SECS);
+
+        for chunk in recent_messages.chunks(BULK_DELETE_LIMIT) {
+            if let [message] = chunk {
+                single_messages.push(message);
+            } else {
+                self.bot
+                    .http
+                    .delete_messages(
+                        chunk[0].channel_id,
+                        &chunk.iter().map(|message| message.id).collect::<Vec<_>>(),
+                    )?
+                    .await?;
+            }
+        }
+
+        for (idx, message) in single_messages.iter().enumerate() {
+            if (idx + 1) % 10 == 0 {
+                println!(
+                    "deleting messages in {guild_id}: {}/{}",
+                    idx + 1,
+                    single_messages.len()
+                );
+            }
+
+            self.bot
+                .http
+                .delete_message(message.channel_id, message.id)
+                .await?;
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        Ok(())
+    }
+
+    /// Delete the copies through the webhooks that sent them, which doesn't need
+    /// **Manage Messages** permissions in their channel
+    pub async fn delete_copies(&self, copies: &[Message]) -> Result<()> {
+        let mut webhooks: Vec<Webhook> = vec![];
+
+        for copy in copies {
+            let webhook_id = copy.webhook_id.ok()?;
+            let webhook =
+                if let Some(webhook) = webhooks.iter().find(|webhook| webhook.id == webhook_id) {
+                    webhook
+                } else {
+                    let webhook = self.bot.http.webhook(webhook_id).await?.model().await?;
+                    webhooks.push(webhook);
+                    webhooks.last().ok()?
+                };
+
+            let mut delete_webhook_message = self.bot.http.delete_webhook_message(
+                webhook.id,
+                webhook.token.as_deref().ok()?,
+                copy.id,
+            );
+            if copy.channel_id != webhook.channel_id {
+                delete_webhook_message = delete_webhook_message.thread_id(copy.channel_id);
+            }
+            delete_webhook_message.await?;
+        }
+
+        Ok(())
+    }
+
+    /// The webhook the bot can execute in the channel, creating one if it
+    /// doesn't exist
+    async fn webhook(&self, channel_id: Id<ChannelMarker>) -> Result<Webhook> {
         let webhook = match self
             .bot
             .http
@@ -37,59 +280,227 @@ impl Context {
                     .await?
             }
         };
-        let webhook_token = webhook.token.ok()?;
 
-        let mut execute_webhook = self
-            .bot
-            .http
-            .execute_webhook(webhook.id, &webhook_token)
-            .content(&message.content)
-            .map_err(|_| CustomError::MessageTooLong)?
-            .username(
-                message
-                    .member
-                    .as_ref()
-                    .and_then(|member| member.nick.as_ref())
-                    .unwrap_or(&message.author.name),
-            )?;
-
-        if let Some(thread_id) = thread_id {
-            execute_webhook = execute_webhook.thread_id(thread_id);
+        Ok(webhook)
+    }
+
+    /// Download the message's attachments and stickers to upload them again,
+    /// since webhooks can't send stickers
+    ///
+    /// Attachments and stickers that don't fit in the guild's upload limit are
+    /// returned as links instead, stickers that aren't images are returned as
+    /// their names
+    async fn reupload_attachments(
+        &self,
+        message: &Message,
+        guild_id: Id<GuildMarker>,
+    ) -> Result<(Vec<Attachment>, Vec<String>)> {
+        let mut attachments = vec![];
+        let mut links = vec![];
+
+        if message.attachments.is_empty() && message.sticker_items.is_empty() {
+            return Ok((attachments, links));
         }
 
-        if let Some(avatar_url) = message
-            .member
-            .as_ref()
-            .and_then(|member| member.avatar)
-            .zip(message.guild_id)
-            .map(|(avatar, guild_id)| {
+        let mut remaining_size = upload_limit(
+            self.bot
+                .http
+                .guild(guild_id)
+                .await?
+                .model()
+                .await?
+                .premium_tier,
+        );
+
+        for (id, attachment) in (0..).zip(&message.attachments) {
+            if attachment.size > remaining_size {
+                if attachment.filename.starts_with("SPOILER_") {
+                    links.push(format!("||{}||", attachment.url));
+                } else {
+                    links.push(attachment.url.clone());
+                }
+                continue;
+            }
+            remaining_size -= attachment.size;
+
+            let file = self
+                .reqwest
+                .get(&attachment.url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec();
+
+            let mut reupload = Attachment::from_bytes(attachment.filename.clone(), file, id);
+            if let Some(description) = &attachment.description {
+                reupload.description(description.clone());
+            }
+
+            attachments.push(reupload);
+        }
+
+        for (id, sticker) in (message.attachments.len()..).zip(&message.sticker_items) {
+            let extension = match sticker.format_type {
+                StickerFormatType::Png | StickerFormatType::Apng => "png",
+                StickerFormatType::Gif => "gif",
+                _ => {
+                    links.push(format!("-# sticker: {}", sticker.name));
+                    continue;
+                }
+            };
+            let url = format!(
+                "https://media.discordapp.net/stickers/{}.{extension}",
+                sticker.id
+            );
+
+            let file = self
+                .reqwest
+                .get(&url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?
+                .to_vec();
+            let size = u64::try_from(file.len())?;
+
+            if size > remaining_size || attachments.len() == ATTACHMENT_COUNT_LIMIT {
+                links.push(url);
+                continue;
+            }
+            remaining_size -= size;
+
+            let mut reupload = Attachment::from_bytes(
+                format!("{}.{extension}", sticker.name),
+                file,
+                id.try_into()?,
+            );
+            reupload.description(sticker.name.clone());
+            attachments.push(reupload);
+        }
+
+        Ok((attachments, links))
+    }
+}
+
+/// Add the line to the end of the content
+fn push_line(content: &mut String, line: &str) {
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content.push_str(line);
+}
+
+/// The line showing when the message was sent and whether it was edited
+///
+/// Returns `None` if the message is a copy that already shows when the
+/// original was sent, such as when undoing a move
+fn timestamp_footer(message: &Message) -> Option<String> {
+    if message
+        .content
+        .lines()
+        .any(|line| line.starts_with(TIMESTAMP_FOOTER_PREFIX))
+    {
+        return None;
+    }
+
+    let mut footer = format!(
+        "{TIMESTAMP_FOOTER_PREFIX}{}:f>",
+        message.timestamp.as_secs()
+    );
+    if message.edited_timestamp.is_some() {
+        footer.push_str(" (edited)");
+    }
+
+    Some(footer)
+}
+
+/// The URL of the author's avatar, preferring their guild avatar
+fn avatar_url(message: &Message) -> Option<String> {
+    message
+        .member
+        .as_ref()
+        .and_then(|member| member.avatar)
+        .zip(message.guild_id)
+        .map(|(avatar, guild_id)| {
+            format!(
+                "https://cdn.discordapp.com/guilds/{guild_id}/users/{}/avatar/{}.png",
+                message.author.id, avatar
+            )
+        })
+        .or_else(|| {
+            message.author.avatar.map(|avatar| {
                 format!(
-                    "https://cdn.discordapp.com/guilds/{guild_id}/users/{}/avatar/{}.png",
+                    "https://cdn.discordapp.com/avatars/{}/{}.png",
                     message.author.id, avatar
                 )
             })
-            .or_else(|| {
-                message.author.avatar.map(|avatar| {
-                    format!(
-                        "https://cdn.discordapp.com/avatars/{}/{}.png",
-                        message.author.id, avatar
-                    )
-                })
-            })
+        })
+}
+
+/// The embeds to send with the copy of the message
+///
+/// Link previews of links in the content are left for Discord to generate
+/// again, other embeds are sent as rich embeds after the content embed if
+/// there's one
+fn embeds(message: &Message, content_embed: Option<Embed>) -> Vec<Embed> {
+    let mut total_length = content_embed.as_ref().map_or(0, embed::chars);
+    let mut embeds = content_embed.into_iter().collect::<Vec<_>>();
+
+    for embed in &message.embeds {
+        if embed.kind != "rich"
+            && embed
+                .url
+                .as_ref()
+                .is_some_and(|url| message.content.contains(url.trim_end_matches('/')))
         {
-            execute_webhook.avatar_url(&avatar_url).await?;
-        } else {
-            execute_webhook.await?;
+            continue;
         }
 
-        Ok(())
+        let length = embed::chars(embed);
+        if embeds.len() == EMBED_COUNT_LIMIT || total_length + length > EMBED_TOTAL_LENGTH {
+            break;
+        }
+        total_length += length;
+
+        let image = embed.image.clone().or_else(|| {
+            matches!(embed.kind.as_str(), "image" | "gifv")
+                .then_some(embed.thumbnail.as_ref())
+                .flatten()
+                .map(|thumbnail| EmbedImage {
+                    height: thumbnail.height,
+                    proxy_url: thumbnail.proxy_url.clone(),
+                    url: thumbnail.url.clone(),
+                    width: thumbnail.width,
+                })
+        });
+        let thumbnail = embed
+            .thumbnail
+            .clone()
+            .filter(|EmbedThumbnail { url, .. }| {
+                image.as_ref().is_none_or(|image| &image.url != url)
+            });
+
+        embeds.push(Embed {
+            image,
+            thumbnail,
+            kind: "rich".to_owned(),
+            provider: None,
+            video: None,
+            ..embed.clone()
+        });
     }
+
+    embeds
 }
 
-pub fn check(message: &Message) -> Result<()> {
-    if !message.attachments.is_empty() {
-        return Err(CustomError::MessageAttachment.into());
+/// The maximum total size of files in a message in bytes
+pub const fn upload_limit(premium_tier: PremiumTier) -> u64 {
+    match premium_tier {
+        PremiumTier::Tier2 => 50 * 1024 * 1024,
+        PremiumTier::Tier3 => 100 * 1024 * 1024,
+        _ => 10 * 1024 * 1024,
     }
-
-    Ok(())
 }
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use twilight_http::{error::ErrorType, request::channel::reaction::RequestReactionType};
+use twilight_model::channel::message::{Message, ReactionType};
+
+use crate::Context;
+
+/// The line to put after the content of the copy, listing the reactions by
+/// users other than the bot, which can't be added to the copy
+pub fn reaction_summary(message: &Message) -> Option<String> {
+    let reactions = message
+        .reactions
+        .iter()
+        .filter_map(|reaction| {
+            let count = reaction.count - u64::from(reaction.me);
+            (count > 0).then(|| format!("{} {count}", emoji(&reaction.emoji)))
+        })
+        .collect::<Vec<_>>();
+
+    (!reactions.is_empty()).then(|| format!("-# reactions: {}", reactions.join(", ")))
+}
+
+impl Context {
+    /// Add the reactions on the message to its copy
+    ///
+    /// Reactions the bot can't add, such as custom emojis from servers it's not
+    /// in, are skipped
+    pub async fn add_reactions(&self, message: &Message, copy: &Message) -> Result<()> {
+        for reaction in &message.reactions {
+            let request_emoji = match &reaction.emoji {
+                ReactionType::Custom { id, name, .. } => RequestReactionType::Custom {
+                    id: *id,
+                    name: name.as_deref(),
+                },
+                ReactionType::Unicode { name } => RequestReactionType::Unicode { name },
+            };
+
+            match self
+                .bot
+                .http
+                .create_reaction(copy.channel_id, copy.id, &request_emoji)
+                .await
+            {
+                Ok(_) => {}
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        ErrorType::Response { status, .. }
+                            if matches!(status.get(), 400 | 403)
+                    ) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// How to show the emoji in the message content
+fn emoji(emoji: &ReactionType) -> String {
+    match emoji {
+        ReactionType::Custom {
+            animated,
+            id,
+            name: Some(name),
+        } => format!("<{}:{name}:{id}>", if *animated { "a" } else { "" }),
+        ReactionType::Custom { name: None, .. } => ":question:".to_owned(),
+        ReactionType::Unicode { name } => name.clone(),
+    }
+}
@@ -0,0 +1,75 @@
+use twilight_model::{
+    channel::{message::MessageType, Message},
+    id::{marker::MessageMarker, Id},
+};
+
+/// The maximum number of characters of the replied message to quote
+const QUOTE_LENGTH_MAX: usize = 100;
+
+/// The copy of the message the message replies to, if it's in `copies`
+pub fn replied_copy<'a>(
+    message: &Message,
+    copies: &'a [(Id<MessageMarker>, Vec<Message>)],
+) -> Option<&'a Message> {
+    let replied_id = message.reference.as_ref()?.message_id?;
+
+    copies
+        .iter()
+        .find(|(original_id, _)| *original_id == replied_id)
+        .and_then(|(_, copies)| copies.first())
+}
+
+/// The line to put before the content of the copy of a reply, quoting the
+/// replied message and linking to it, or to its copy if it's given
+pub fn reply_header(message: &Message, replied_copy: Option<&Message>) -> Option<String> {
+    if message.kind != MessageType::Reply {
+        return None;
+    }
+    let reference = message.reference.as_ref()?;
+    let guild_id = reference.guild_id.or(message.guild_id)?;
+
+    let Some(replied) = message.referenced_message.as_deref() else {
+        return Some("> replying to a deleted message".to_owned());
+    };
+
+    let (channel_id, message_id) = replied_copy.map_or((replied.channel_id, replied.id), |copy| {
+        (copy.channel_id, copy.id)
+    });
+
+    let first_line = escape_mentions(
+        replied.content.lines().next().unwrap_or_default().trim(),
+        replied,
+    );
+    let mut quote = first_line
+        .chars()
+        .take(QUOTE_LENGTH_MAX)
+        .collect::<String>();
+    if quote.is_empty() {
+        quote.push_str("*click to see the message*");
+    } else if quote.len() < first_line.len() || replied.content.lines().nth(1).is_some() {
+        quote.push('…');
+    }
+
+    Some(format!(
+        "> replying to **{}**: {quote} \
+         [jump](https://discord.com/channels/{guild_id}/{channel_id}/{message_id})",
+        replied.author.name
+    ))
+}
+
+/// The text with user mentions replaced with the users' names, so that they
+/// don't ping again when user mentions are allowed
+///
+/// Mentions of users not in the message's mentions are broken with a zero
+/// width space
+fn escape_mentions(text: &str, message: &Message) -> String {
+    let mut escaped = text.to_owned();
+    for mention in &message.mentions {
+        let name = format!("@{}", mention.name);
+        escaped = escaped
+            .replace(&format!("<@{}>", mention.id), &name)
+            .replace(&format!("<@!{}>", mention.id), &name);
+    }
+
+    escaped.replace("<@", "<@\u{200b}")
+}
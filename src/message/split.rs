@@ -0,0 +1,165 @@
+use twilight_validate::message::MESSAGE_CONTENT_LENGTH_MAX;
+
+const CODE_BLOCK_FENCE: &str = "```";
+
+/// Split the content into parts that fit in a message
+///
+/// Prefers splitting at newlines, then at whitespace, never inside code blocks,
+/// inline code or mentions, if a code block doesn't fit in a message, it's
+/// closed at the end of the part and opened again in the next part
+pub fn split(content: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut rest = content.to_owned();
+
+    while rest.chars().count() > MESSAGE_CONTENT_LENGTH_MAX {
+        // leave room to close a code block
+        let idx = split_index(
+            &rest,
+            MESSAGE_CONTENT_LENGTH_MAX - CODE_BLOCK_FENCE.len() - 1,
+        );
+
+        let mut part = rest[..idx].to_owned();
+        let mut next = rest[idx..].to_owned();
+        if next.starts_with(char::is_whitespace) {
+            next.remove(0);
+        }
+
+        if let Some(language) = open_code_block_language(&part).map(str::to_owned) {
+            part.push('\n');
+            part.push_str(CODE_BLOCK_FENCE);
+            next = format!("{CODE_BLOCK_FENCE}{language}\n{next}");
+        }
+
+        parts.push(part);
+        rest = next;
+    }
+    parts.push(rest);
+
+    parts
+}
+
+/// The byte index to split the content at so that the first part is at most
+/// `max_len` characters
+fn split_index(content: &str, max_len: usize) -> usize {
+    let max_idx = content
+        .char_indices()
+        .nth(max_len)
+        .map_or(content.len(), |(idx, _)| idx);
+    let window = &content[..max_idx];
+
+    let last_matching = |is_split_char: fn(char) -> bool, allow_code: bool| {
+        window
+            .char_indices()
+            .rev()
+            .filter(|&(idx, char)| idx > 0 && is_split_char(char))
+            .map(|(idx, _)| idx)
+            .find(|&idx| allow_code || !is_in_code(&content[..idx]))
+    };
+
+    last_matching(|char| char == '\n', false)
+        .or_else(|| last_matching(char::is_whitespace, false))
+        .or_else(|| last_matching(|char| char == '\n', true))
+        .unwrap_or_else(|| {
+            // mentions don't contain whitespace, so only a hard split can be
+            // inside one
+            window
+                .rfind('<')
+                .filter(|&mention_idx| mention_idx > 0 && !window[mention_idx..].contains('>'))
+                .unwrap_or(max_idx)
+        })
+}
+
+/// Whether the end of the content is inside a code block or inline code
+fn is_in_code(content: &str) -> bool {
+    open_code_block_language(content).is_some()
+        || !content
+            .replace(CODE_BLOCK_FENCE, "")
+            .matches('`')
+            .count()
+            .is_multiple_of(2)
+}
+
+/// The language of the code block the end of the content is in, if it's in
+/// one
+fn open_code_block_language(content: &str) -> Option<&str> {
+    if content.matches(CODE_BLOCK_FENCE).count().is_multiple_of(2) {
+        return None;
+    }
+
+    let (_, after_fence) = content.rsplit_once(CODE_BLOCK_FENCE)?;
+    Some(
+        after_fence
+            .split_once('\n')
+            .map_or("", |(language, _)| language.trim()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_validate::message::MESSAGE_CONTENT_LENGTH_MAX;
+
+    use super::split;
+
+    fn assert_parts_fit(parts: &[String]) {
+        for part in parts {
+            assert!(part.chars().count() <= MESSAGE_CONTENT_LENGTH_MAX);
+        }
+    }
+
+    #[test]
+    fn splits_at_newline() {
+        let content = format!("{}\n{}", "a".repeat(1500), "b".repeat(1000));
+
+        assert_eq!(split(&content), ["a".repeat(1500), "b".repeat(1000)]);
+    }
+
+    #[test]
+    fn splits_at_whitespace_without_newlines() {
+        let content = vec!["word"; 500].join(" ");
+        let parts = split(&content);
+
+        assert_eq!(parts.len(), 2);
+        assert_parts_fit(&parts);
+        assert!(parts[0].ends_with("word"));
+        assert!(parts[1].starts_with("word"));
+        assert_eq!(parts.join(" "), content);
+    }
+
+    #[test]
+    fn reopens_code_block_with_language() {
+        let content = format!("```rust\n{}```", "let x = 1;\n".repeat(250));
+        let parts = split(&content);
+
+        assert_eq!(parts.len(), 2);
+        assert_parts_fit(&parts);
+        assert!(parts[0].starts_with("```rust\n"));
+        assert!(parts[0].ends_with("\n```"));
+        assert!(parts[1].starts_with("```rust\nlet x = 1;\n"));
+        assert!(parts[1].ends_with("```"));
+    }
+
+    #[test]
+    fn doesnt_split_mention_in_long_word() {
+        let mention = "<@123456789012345678>";
+        let content = format!("{}{mention}{}", "a".repeat(1990), "b".repeat(100));
+        let parts = split(&content);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], "a".repeat(1990));
+        assert!(parts[1].starts_with(mention));
+        assert_eq!(parts.concat(), content);
+    }
+
+    #[test]
+    fn counts_multibyte_characters() {
+        let content = "é".repeat(MESSAGE_CONTENT_LENGTH_MAX);
+        assert_eq!(split(&content), [content]);
+
+        let content = "é".repeat(MESSAGE_CONTENT_LENGTH_MAX + 1);
+        let parts = split(&content);
+
+        assert_eq!(parts.len(), 2);
+        assert_parts_fit(&parts);
+        assert_eq!(parts.concat(), content);
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use sparkle_convenience::{error::IntoError, prettify::Prettify};
+use twilight_http::error::ErrorType;
+use twilight_model::{
+    channel::{permission_overwrite::PermissionOverwrite, Channel, ChannelType},
+    guild::{Guild, PartialMember, Permissions},
+    id::{
+        marker::{GuildMarker, RoleMarker, UserMarker},
+        Id,
+    },
+};
+use twilight_util::permission_calculator::PermissionCalculator;
+
+use crate::{Context, CustomError};
+
+/// The permissions the member needs in the channel they move messages from
+pub const SOURCE_MEMBER_PERMISSIONS: Permissions =
+    Permissions::VIEW_CHANNEL.union(Permissions::READ_MESSAGE_HISTORY);
+
+/// The permissions the bot needs in the channel it moves messages to
+///
+/// Adding reactions isn't required, the reactions the bot can't add are skipped
+pub const DESTINATION_BOT_PERMISSIONS: Permissions =
+    Permissions::VIEW_CHANNEL.union(Permissions::MANAGE_WEBHOOKS);
+
+impl Context {
+    /// Return an error naming the permissions the member or the bot is missing
+    /// in the channel, returning the member's permissions in it otherwise
+    ///
+    /// The guild, the thread's parent and the bot's member are only fetched
+    /// once for both
+    pub async fn check_permissions(
+        &self,
+        channel: &Channel,
+        guild_id: Id<GuildMarker>,
+        member: &PartialMember,
+        member_required: Permissions,
+        bot_required: Permissions,
+    ) -> Result<Permissions> {
+        let guild = self.bot.http.guild(guild_id).await?.model().await?;
+        let permission_overwrites = if channel.kind.is_thread() {
+            self.bot
+                .http
+                .channel(channel.parent_id.ok()?)
+                .await?
+                .model()
+                .await?
+                .permission_overwrites
+                .ok()?
+        } else {
+            channel.permission_overwrites.clone().ok()?
+        };
+        let bot_member = self
+            .bot
+            .http
+            .guild_member(guild_id, self.bot.user.id)
+            .await?
+            .model()
+            .await?;
+
+        let member_id = member.user.as_ref().ok()?.id;
+        let member_permissions = permissions(
+            &guild,
+            channel.kind,
+            &permission_overwrites,
+            member_id,
+            &member.roles,
+        )?;
+        self.check_member_permissions(channel, member_id, member_permissions, member_required)
+            .await?;
+
+        let bot_permissions = permissions(
+            &guild,
+            channel.kind,
+            &permission_overwrites,
+            self.bot.user.id,
+            &bot_member.roles,
+        )?;
+        self.check_bot_permissions(channel, bot_permissions, bot_required)
+            .await?;
+
+        Ok(member_permissions)
+    }
+
+    /// Return an error naming the permissions the member is missing in the
+    /// channel or if they can't view the private thread
+    pub async fn check_member_permissions(
+        &self,
+        channel: &Channel,
+        member_id: Id<UserMarker>,
+        permissions: Permissions,
+        required: Permissions,
+    ) -> Result<()> {
+        let missing = required - permissions;
+        if missing.contains(Permissions::VIEW_CHANNEL)
+            || !self
+                .can_view_thread(channel, member_id, permissions)
+                .await?
+        {
+            return Err(CustomError::ChannelHiddenFromMember(channel.id).into());
+        }
+        if !missing.is_empty() {
+            return Err(
+                CustomError::MemberPermissionsMissing(channel.id, missing.prettify()).into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Return an error naming the permissions the bot is missing in the channel
+    /// or if it can't view the private thread
+    pub async fn check_bot_permissions(
+        &self,
+        channel: &Channel,
+        permissions: Permissions,
+        required: Permissions,
+    ) -> Result<()> {
+        let missing = required - permissions;
+        if missing.contains(Permissions::VIEW_CHANNEL) {
+            return Err(CustomError::ChannelHiddenFromBot(channel.id).into());
+        }
+        if !self
+            .can_view_thread(channel, self.bot.user.id, permissions)
+            .await?
+        {
+            return Err(CustomError::BotNotInThread(channel.id).into());
+        }
+        if !missing.is_empty() {
+            return Err(CustomError::BotPermissionsMissing(channel.id, missing.prettify()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Whether the user is in the channel if it's a private thread, users with
+    /// **Manage Threads** permissions can view private threads without being
+    /// in them
+    async fn can_view_thread(
+        &self,
+        channel: &Channel,
+        user_id: Id<UserMarker>,
+        permissions: Permissions,
+    ) -> Result<bool> {
+        if channel.kind != ChannelType::PrivateThread
+            || permissions.contains(Permissions::MANAGE_THREADS)
+        {
+            return Ok(true);
+        }
+
+        match self.bot.http.thread_member(channel.id, user_id).await {
+            Ok(_) => Ok(true),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorType::Response { status, .. } if status.get() == 404
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// The permissions of the user with the roles in the channel with the
+/// permission overwrites, which are the parent's if it's a thread
+fn permissions(
+    guild: &Guild,
+    channel_kind: ChannelType,
+    permission_overwrites: &[PermissionOverwrite],
+    user_id: Id<UserMarker>,
+    role_ids: &[Id<RoleMarker>],
+) -> Result<Permissions> {
+    let everyone_role = guild
+        .roles
+        .iter()
+        .find_map(|role| (role.id.cast() == guild.id).then_some(role.permissions))
+        .ok()?;
+
+    let member_roles = guild
+        .roles
+        .iter()
+        .filter_map(|role| {
+            role_ids
+                .contains(&role.id)
+                .then_some((role.id, role.permissions))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(
+        PermissionCalculator::new(guild.id, user_id, everyone_role, &member_roles)
+            .owner_id(guild.owner_id)
+            .in_channel(channel_kind, permission_overwrites),
+    )
+}